@@ -0,0 +1,106 @@
+//! Compares index lookups against a linear scan over a synthetic library of 50k papers.
+//!
+//! Run with `cargo bench --bench index`.
+#![allow(dead_code)]
+
+#[path = "../src/library/index.rs"]
+mod index;
+#[path = "../src/query.rs"]
+mod query;
+
+use std::time::{Duration, Instant};
+
+use index::Index;
+use query::{Query, QueryTerm};
+
+const PAPERS: usize = 50_000;
+const ROUNDS: u32 = 20;
+
+const WORDS: &[&str] = &[
+    "online", "scheduling", "learning", "augmented", "algorithms", "approximation", "graph",
+    "matching", "random", "streaming", "parallel", "flow", "cut", "bounds", "lower", "upper",
+    "metric", "tree", "network", "design", "caching", "paging", "knapsack", "submodular",
+    "convex", "optimization", "games", "equilibria", "auctions", "mechanism", "fair", "robust",
+    "stochastic", "queueing", "routing", "packing", "covering", "facility", "location", "server",
+];
+const AUTHORS: &[&str] = &[
+    "alexander", "lindermayr", "nicole", "megow", "anupam", "gupta", "ravi", "kumar", "amit",
+    "sahai", "sven", "jager", "bertrand", "simon", "leen", "stougie", "marek", "eliáš",
+];
+const VENUES: &[&str] = &["soda", "stoc", "focs", "icalp", "esa", "ipco", "corr", "neurips"];
+
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % bound as u64) as usize
+    }
+}
+
+fn synthetic_library() -> Vec<Vec<String>> {
+    let mut rng = Lcg(42);
+    (0..PAPERS)
+        .map(|_| {
+            let mut tokens: Vec<String> = (0..6 + rng.next(6))
+                .map(|_| WORDS[rng.next(WORDS.len())].to_owned())
+                .collect();
+            for _ in 0..2 + rng.next(4) {
+                tokens.push(AUTHORS[rng.next(AUTHORS.len())].to_owned());
+            }
+            tokens.push(VENUES[rng.next(VENUES.len())].to_owned());
+            tokens.push((1990 + rng.next(35)).to_string());
+            tokens
+        })
+        .collect()
+}
+
+fn linear_scan(papers: &[Vec<String>], query: &Query) -> usize {
+    papers
+        .iter()
+        .filter(|tokens| {
+            query.into_iter().all(|term| match term {
+                QueryTerm::Exact(q) => tokens.iter().any(|t| q == t),
+                QueryTerm::Prefix(q) => tokens.iter().any(|t| t.starts_with(q)),
+                QueryTerm::Filter(_) => true,
+            })
+        })
+        .count()
+}
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut hits = 0;
+    for _ in 0..ROUNDS {
+        hits = f();
+    }
+    (start.elapsed() / ROUNDS, hits)
+}
+
+fn main() {
+    let papers = synthetic_library();
+
+    let start = Instant::now();
+    let mut index = Index::default();
+    for (i, tokens) in papers.iter().enumerate() {
+        index.insert(i, tokens.iter().cloned());
+    }
+    println!("building index of {} papers: {:?}", PAPERS, start.elapsed());
+
+    for text in ["s", "sched", "online sched", "lindermayr soda", "graph$ 2020$", "xyz"] {
+        let query = Query::from(text.to_owned());
+        let (scan, scan_hits) = time(|| linear_scan(&papers, &query));
+        let (lookup, lookup_hits) = time(|| index.search(&query).map_or(PAPERS, |r| r.len()));
+        assert_eq!(scan_hits, lookup_hits);
+        println!(
+            "{:<20} {:>6} hits   scan {:>12?}   index {:>12?}",
+            format!("{:?}", text),
+            scan_hits,
+            scan,
+            lookup
+        );
+    }
+}
@@ -1,157 +1,241 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
-use console::{style, Term};
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
-use lopdf::{Document, Object};
-
-use super::util;
+use console::style;
 
 use crate::{
-    config,
-    library::{Library, LocalPaper},
-    Identifier, PaperInfo, PaperTitle, Query,
+    identify::{self, Found},
+    library::{Attachment, AttachmentRole, Library, LocalPaper},
+    pdf::{self, PdfInfo},
+    resolve,
+    util::{confirm, prompt},
+    xiv_config::Config,
+    Identifier, PaperInfo, PaperTitle, Query, Venue,
 };
 
 #[derive(Parser, Debug)]
 #[clap(about = "Add a local PDF to your library")]
 pub struct Add {
-    #[clap(parse(from_os_str))]
+    /// PDF file to add
     pdf_file: PathBuf,
+
+    /// Role of the file, e.g. main, supplement, slides or arxiv-v2. Defaults to main, or to the
+    /// arXiv version of a paper found on arXiv.
+    #[clap(short, long)]
+    role: Option<AttachmentRole>,
 }
 
 impl Add {
-    pub fn run(&self) -> Result<()> {
-        if self.pdf_file.is_file() && "pdf" == self.pdf_file.extension().unwrap() {
-            let data_dir = config::xivar_data_dir()?;
-            let mut lib = Library::open(&data_dir)?;
-            if lib.find_paper_by_path(&self.pdf_file).is_some() && !Confirm::new().with_prompt("This paper is already in your library! You can add another entry to your library by using another ID. Continue?").default(false).interact()? {
+    pub async fn run(&self, config: Config) -> Result<()> {
+        let is_pdf = self
+            .pdf_file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        if !self.pdf_file.is_file() || !is_pdf {
+            bail!("{} is no PDF file!", self.pdf_file.display());
+        }
+        let path = self
+            .pdf_file
+            .canonicalize()
+            .with_context(|| format!("cannot open {}", self.pdf_file.display()))?;
+
+        let mut lib = Library::open(&config)?;
+        if let Some(paper) = lib.find_paper_by_path(&path) {
+            println!("This file already belongs to {}", paper);
+            if !confirm("Add it to another paper?")? {
                 return Ok(());
             }
+        }
 
-            let spinner = indicatif::ProgressBar::new_spinner();
-            spinner.set_style(
-                indicatif::ProgressStyle::default_spinner().template("{msg} {spinner:.cyan/blue} "),
-            );
-            spinner.set_message("Reading PDF");
-            spinner.enable_steady_tick(10);
-
-            let doc = Document::load(&self.pdf_file)?;
-            let authors = get_author(&doc);
-            let title = get_title(&doc);
-
-            spinner.finish_and_clear();
-
-            if let Some(ref title) = title {
-                if lib
-                    .iter_matches(&Query::builder().terms(vec![title.to_owned()]).build())
-                    .count()
-                    > 0
-                {
-                    println!(
-                        "Note that there already is a paper with the title {} in your library!",
-                        style(title).bold().cyan()
-                    );
-                }
+        let info = match pdf::read_info(&path) {
+            Ok(info) => info,
+            Err(err) => {
+                println!("{:#}", err);
+                PdfInfo::default()
             }
-
-            let mut options = vec![
-                "Enter metadata manually".to_owned(),
-                "Search online".to_owned(),
-            ];
-            if let Some(ref title) = title {
-                options.push(format!("Search title \"{}\" online", style(title).bold()));
+        };
+        let title = identify::title(&info);
+        let ids = identify::identifiers(&path, &info);
+        if let Some(title) = &title {
+            println!("Title:   {}", style(title).bold());
+            let query = Query::from(title.to_owned());
+            let existing = lib.iter_matches(&query).next().cloned();
+            if let Some(paper) = existing {
+                println!(
+                    "{}",
+                    style(format!("Note that your library already contains {}", paper)).dim()
+                );
             }
+        }
+        if !info.authors.is_empty() {
+            println!("Authors: {}", info.authors.join(", "));
+        }
+        if !ids.is_empty() {
+            let ids: Vec<String> = ids.iter().map(|f| f.id.to_prefixed_string()).collect();
+            println!("Found:   {}", ids.join(", "));
+        }
 
-            loop {
-                let selection = Select::with_theme(&ColorfulTheme::default())
-                    .items(&options)
-                    .default(0)
-                    .interact_on_opt(&Term::stderr())?;
-                let paper_info = match selection {
-                    Some(0) => enter_manually(title.as_deref(), authors.as_deref()),
-                    Some(1) => {
-                        let search_string: String =
-                            Input::new().with_prompt("Query").interact_text()?;
-                        let paper = util::search_and_select(&lib, vec![search_string], None)?;
-                        util::select_hit(paper).map(|hit| hit.metadata().clone())
+        let mut paper = loop {
+            // at most three identifiers, the others are most likely references
+            let mut options: Vec<Lookup> = (0..ids.len().min(3)).map(Lookup::Id).collect();
+            if title.is_some() {
+                options.push(Lookup::Title);
+            }
+            options.extend([Lookup::Query, Lookup::Manual]);
+            for (i, option) in options.iter().enumerate() {
+                println!("  ({}) {}", i + 1, option.label(&ids));
+            }
+            let choice = prompt("Choose or (q)uit", Some("1"))?;
+            let option = match choice.parse::<usize>() {
+                Ok(i) if i >= 1 && i <= options.len() => options[i - 1],
+                _ if choice == "q" => return Ok(()),
+                _ => {
+                    println!("Invalid choice!");
+                    continue;
+                }
+            };
+
+            let found = match option {
+                Lookup::Id(i) => match resolve::lookup(&ids[i].id, &path).await {
+                    Ok(Some(paper)) => {
+                        let found = paper.metadata();
+                        println!("Found {} {} {}", found, found.venue, found.year);
+                        if confirm("Is this the paper?")? {
+                            Some(paper)
+                        } else {
+                            None
+                        }
                     }
-                    Some(2) => util::search_and_select(
-                        &lib,
-                        vec![title.as_deref().unwrap().to_owned()],
-                        None,
-                    )
-                    .map(|paper| paper.metadata().clone()),
-                    _ => bail!("Aborting!"),
-                };
-                if let Ok(paper_info) = paper_info {
-                    let paper = LocalPaper {
-                        metadata: paper_info,
-                        location: self.pdf_file.clone(),
-                        ees: vec![],
-                    };
-                    lib.add(paper);
-                    println!("{}", style("Added paper to library!").green().bold());
-                    break;
+                    Ok(None) => {
+                        println!("Nothing found online.");
+                        None
+                    }
+                    Err(err) => {
+                        println!("{}", err);
+                        None
+                    }
+                },
+                Lookup::Title => {
+                    let title = title.as_deref().unwrap_or_default();
+                    search_and_select(title, &info.authors, &path).await?
                 }
+                Lookup::Query => {
+                    let query = prompt("Query", None)?;
+                    search_and_select(&query, &[], &path).await?
+                }
+                Lookup::Manual => Some(enter_manually(title.as_deref(), &info, &path)?),
+            };
+            if let Some(paper) = found {
+                break paper;
             }
-        } else {
-            println!("{}", style("The given file is no PDF!").red().bold());
-        }
+        };
 
-        Ok(())
+        if let Some(role) = self.role {
+            for attachment in &mut paper.attachments {
+                attachment.role = role;
+            }
+        }
+        println!("{} {}", style("Added").green().bold(), paper.metadata());
+        lib.add(paper);
+        lib.save()
     }
 }
 
-fn enter_manually(title: Option<&str>, authors: Option<&str>) -> Result<PaperInfo> {
-    let title: String = Input::new()
-        .with_prompt("Title")
-        .with_initial_text(title.unwrap_or_default())
-        .interact_text()?;
-    let authors: String = Input::new()
-        .with_prompt("Authors")
-        .with_initial_text(authors.unwrap_or_default())
-        .interact_text()?;
-    let year: String = Input::new().with_prompt("Year").interact_text()?;
-    let venue: String = Input::new().with_prompt("Venue").interact_text()?;
-    let id: Option<String> = Input::new().with_prompt("Identifier").interact_text().ok();
-    Ok(PaperInfo {
-        id: id.map(Identifier::Custom),
-        title: PaperTitle::new(title),
-        authors: authors.split(",").map(|a| a.trim().to_owned()).collect(),
-        year,
-        venue,
-    })
+#[derive(Clone, Copy)]
+enum Lookup {
+    /// Look up the identifier found in the file with the index.
+    Id(usize),
+    Title,
+    Query,
+    Manual,
 }
 
-fn get_title(doc: &Document) -> Option<String> {
-    get_info_field(doc, "Title")
+impl Lookup {
+    fn label(self, ids: &[Found]) -> String {
+        match self {
+            Lookup::Id(i) => format!("Look up {}", ids[i].id.to_prefixed_string()),
+            Lookup::Title => "Search the title online".to_owned(),
+            Lookup::Query => "Search online".to_owned(),
+            Lookup::Manual => "Enter metadata manually".to_owned(),
+        }
+    }
 }
 
-fn get_author(doc: &Document) -> Option<String> {
-    get_info_field(doc, "Author")
+/// Searches DBLP and arXiv and asks which of the found papers the file at `path` is.
+async fn search_and_select(
+    query: &str,
+    authors: &[String],
+    path: &Path,
+) -> Result<Option<LocalPaper>> {
+    let mut candidates = match resolve::search(query, authors, path).await {
+        Ok(candidates) => candidates,
+        Err(err) => {
+            println!("{}", err);
+            return Ok(None);
+        }
+    };
+    candidates.truncate(10);
+    if candidates.is_empty() {
+        println!("Nothing found online.");
+        return Ok(None);
+    }
+    for (i, candidate) in candidates.iter().enumerate() {
+        let info = candidate.paper.metadata();
+        println!("  ({}) {} {} {}", i + 1, info, info.venue, info.year);
+    }
+    loop {
+        let choice = prompt("Select a paper or go (b)ack", Some("1"))?;
+        match choice.parse::<usize>() {
+            Ok(i) if i >= 1 && i <= candidates.len() => {
+                return Ok(Some(candidates.swap_remove(i - 1).paper))
+            }
+            _ if choice == "b" => return Ok(None),
+            _ => println!("Invalid choice!"),
+        }
+    }
 }
 
-fn get_info_field(doc: &Document, field_name: &str) -> Option<String> {
-    doc.trailer.get(b"Info").ok().and_then(|info| {
-        match *info {
-            Object::Dictionary(ref dict) => Some(dict),
-            Object::Reference(ref id) => doc.objects.get(id).and_then(|o| o.as_dict().ok()),
-            _ => None,
+fn enter_manually(title: Option<&str>, info: &PdfInfo, path: &Path) -> Result<LocalPaper> {
+    let title = prompt("Title", title)?;
+    let authors = prompt(
+        "Authors, separated by commas",
+        Some(&info.authors.join(", ")),
+    )?;
+    let year = prompt("Year", None)?;
+    let venue = prompt("Venue", None)?;
+    let venue = loop {
+        match prompt(
+            "Venue kind (journal, conference, arxiv)",
+            Some("conference"),
+        )?
+        .as_str()
+        {
+            "journal" => break Venue::Journal(venue),
+            "conference" => break Venue::Conf(venue),
+            "arxiv" => break Venue::Arxiv(venue),
+            _ => println!("Invalid venue kind!"),
         }
-        .and_then(|dict| {
-            dict.get(field_name.as_bytes()).ok().and_then(|obj| {
-                let field = std::str::from_utf8(obj.as_str().unwrap())
-                    .unwrap_or_default()
-                    .replace("(", "")
-                    .replace(")", "");
-                if field.is_empty() {
-                    None
-                } else {
-                    Some(field)
-                }
-            })
-        })
-    })
+    };
+    let id = prompt("Identifier, e.g. arxiv:2202.01234 or doi:10.1000/182", None)?;
+
+    let metadata = PaperInfo {
+        id: Some(id)
+            .filter(|id| !id.is_empty())
+            .map(|id| Identifier::parse_prefixed(&id)),
+        title: PaperTitle::new(title),
+        venue,
+        authors: authors
+            .split(',')
+            .map(|a| a.trim().to_owned())
+            .filter(|a| !a.is_empty())
+            .collect(),
+        year,
+        summary: None,
+    };
+    Ok(LocalPaper::new(
+        metadata,
+        vec![Attachment::new(path, AttachmentRole::Main)],
+        vec![],
+    ))
 }
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use crate::{
+    library::{Attachment, AttachmentRole, Library, LocalPaper},
+    util::select_paper,
+    xiv_config::Config,
+    Query,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Attach a file, e.g. slides or supplementary material, to a paper of your library")]
+pub struct Attach {
+    file: PathBuf,
+
+    /// The paper to attach the file to
+    #[clap(required = true)]
+    query: Vec<String>,
+
+    /// One of main, supplement, slides, arxiv or arxiv-v<N>
+    #[clap(short, long, default_value = "main")]
+    role: AttachmentRole,
+
+    /// Remove the file from the paper instead
+    #[clap(long)]
+    remove: bool,
+}
+
+impl Attach {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let mut lib = Library::open(&config)?;
+
+        let query = Query::from(self.query.join(" "));
+        let mut matches: Vec<LocalPaper> = lib.iter_matches(&query).cloned().collect();
+        let mut paper = match matches.len() {
+            0 => bail!("No paper in your library matches the query!"),
+            1 => matches.remove(0),
+            _ => {
+                let i = select_paper(&matches)?;
+                matches.remove(i)
+            }
+        };
+
+        if self.remove {
+            if paper.attachments.len() == 1 {
+                bail!("Cannot remove the only file of {}", paper.metadata());
+            }
+            let path = self
+                .file
+                .canonicalize()
+                .unwrap_or_else(|_| self.file.clone());
+            if !paper.detach(&path) && !paper.detach(&self.file) {
+                bail!(
+                    "{} is not attached to {}",
+                    self.file.display(),
+                    paper.metadata()
+                );
+            }
+            println!("Removed {:?} from {}", self.file, paper.metadata());
+        } else {
+            let path = self
+                .file
+                .canonicalize()
+                .with_context(|| format!("cannot find {}", self.file.display()))?;
+            if !paper.attach(Attachment::new(&path, self.role)) {
+                println!("{:?} is already attached.", path);
+                return Ok(());
+            }
+            println!(
+                "Attached {:?} as {} to {}",
+                path,
+                self.role,
+                paper.metadata()
+            );
+        }
+
+        lib.update(paper);
+        lib.save()
+    }
+}
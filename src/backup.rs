@@ -0,0 +1,112 @@
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use console::style;
+
+use crate::{
+    library::{
+        backup::{self, Backup as Snapshot},
+        Library, LocalPaper,
+    },
+    util::confirm,
+    xiv_config::Config,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "List and restore the snapshots of your library taken on every save")]
+pub struct Backup {
+    #[clap(subcommand)]
+    command: BackupCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum BackupCommand {
+    /// List all snapshots, newest first
+    List,
+    /// Replace your library by a snapshot
+    Restore {
+        /// Number in `xivar backup list` or timestamp of the snapshot
+        id: String,
+
+        /// Restore without asking for confirmation
+        #[clap(short, long)]
+        yes: bool,
+    },
+}
+
+impl Backup {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let backups = backup::list(&config.data_dir)?;
+        match &self.command {
+            BackupCommand::List => {
+                if backups.is_empty() {
+                    println!("No backups yet.");
+                }
+                for (i, backup) in backups.iter().enumerate() {
+                    let size = match backup.read() {
+                        Ok(papers) => format!("{} papers", papers.len()),
+                        Err(err) => format!("unreadable: {}", err),
+                    };
+                    println!("{:>3}  {}  {}", i + 1, backup.id, size);
+                }
+                Ok(())
+            }
+            BackupCommand::Restore { id, yes } => {
+                let backup = find(&backups, id)?;
+                let papers = backup.read()?;
+
+                let mut lib = Library::open(&config)?;
+                let current: Vec<LocalPaper> = lib.iter().cloned().collect();
+                if !print_diff(&current, &papers) {
+                    println!("Your library equals backup {}.", backup.id);
+                    return Ok(());
+                }
+                if !yes && !confirm(&format!("Restore backup {}?", backup.id))? {
+                    return Ok(());
+                }
+
+                lib.restore(papers);
+                lib.save()?;
+                println!("Restored backup {}.", backup.id);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn find<'a>(backups: &'a [Snapshot], id: &str) -> Result<&'a Snapshot> {
+    if let Ok(i) = id.parse::<usize>() {
+        if i >= 1 && i <= backups.len() {
+            return Ok(&backups[i - 1]);
+        }
+    }
+    match backups.iter().find(|b| b.id == id) {
+        Some(backup) => Ok(backup),
+        None => bail!("No backup {}, see `xivar backup list`.", id),
+    }
+}
+
+/// Prints the entries which a restore of `backup` adds, removes or changes.
+/// Returns `false` if there are no differences.
+fn print_diff(current: &[LocalPaper], backup: &[LocalPaper]) -> bool {
+    let mut differs = false;
+    for paper in current {
+        match backup.iter().find(|p| p.metadata() == paper.metadata()) {
+            None => {
+                println!("{} {}", style("-").red().bold(), paper);
+                differs = true;
+            }
+            Some(restored) if restored != paper => {
+                println!("{} {}", style("~").yellow().bold(), restored);
+                differs = true;
+            }
+            Some(_) => {}
+        }
+    }
+    for paper in backup {
+        if !current.iter().any(|p| p.metadata() == paper.metadata()) {
+            println!("{} {}", style("+").green().bold(), paper);
+            differs = true;
+        }
+    }
+    differs
+}
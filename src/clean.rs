@@ -1,30 +1,98 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{library::Library, xiv_config::Config};
+use crate::{
+    library::{Attachment, Library},
+    xiv_config::Config,
+};
 
 #[derive(Parser, Debug)]
 #[clap(about = "Remove non-existent files from your library")]
 pub struct Clean {
     #[clap(long)]
     all: bool,
+
+    /// Search the paper directory and the configured roots for moved files instead of removing them
+    #[clap(long, conflicts_with = "all")]
+    relocate: bool,
+
+    /// Only show what would be changed
+    #[clap(long)]
+    dry_run: bool,
 }
 
 impl Clean {
     pub fn run(&self, config: Config) -> Result<()> {
-        let data_dir = config.data_dir;
-        let mut lib = Library::open(&data_dir)?;
+        let mut lib = Library::open(&config)?;
+
+        if self.relocate {
+            return self.relocate(lib, &config);
+        }
+
+        if self.dry_run {
+            let missing: Vec<&Attachment> = lib
+                .iter()
+                .flat_map(|paper| &paper.attachments)
+                .filter(|a| self.all || !a.exists())
+                .collect();
+            if missing.is_empty() {
+                println!("Nothing to remove.");
+            }
+            for attachment in missing {
+                println!("Would remove {:?}", attachment.path);
+            }
+            return Ok(());
+        }
 
-        let removed = if self.all { lib.clear() } else { lib.clean() };
+        let removed: Vec<Attachment> = if self.all {
+            lib.clear()
+                .into_iter()
+                .flat_map(|paper| paper.attachments)
+                .collect()
+        } else {
+            lib.update_hashes();
+            lib.clean()
+        };
 
         if removed.is_empty() {
             println!("Nothing to remove.");
             Ok(())
         } else {
-            for paper in removed {
-                println!("Removed {:?}", paper.location);
+            for attachment in removed {
+                println!("Removed {:?}", attachment.path);
             }
             lib.save()
         }
     }
+
+    fn relocate(&self, mut lib: Library, config: &Config) -> Result<()> {
+        let mut roots = vec![config.paper_dir.clone()];
+        roots.extend(config.roots.iter().cloned());
+
+        let relocation = lib.relocate(&roots, self.dry_run);
+
+        let (moved, hashed) = if self.dry_run {
+            ("Would move", "Would hash")
+        } else {
+            ("Moved", "Hashed")
+        };
+        if relocation.hashed > 0 {
+            println!("{} {} files.", hashed, relocation.hashed);
+        }
+        for (info, old, new) in &relocation.moved {
+            println!("{} {:?} to {:?} ({})", moved, old, new, info.title);
+        }
+        for (info, path) in &relocation.missing {
+            println!("Missing {:?} ({})", path, info.title);
+        }
+        if relocation.moved.is_empty() && relocation.missing.is_empty() {
+            println!("All files found.");
+        }
+
+        if self.dry_run {
+            Ok(())
+        } else {
+            lib.save()
+        }
+    }
 }
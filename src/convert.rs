@@ -0,0 +1,50 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::{
+    library::{Backend, Library},
+    xiv_config::Config,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Copy your library to another storage backend")]
+pub struct Convert {
+    /// Backend to copy the library to
+    #[clap(value_enum)]
+    to: Backend,
+
+    /// Backend to copy the library from, defaults to the configured backend
+    #[clap(long, value_enum)]
+    from: Option<Backend>,
+}
+
+impl Convert {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let from = self.from.unwrap_or(config.backend);
+        if from == self.to {
+            bail!("Source and target backend are the same!");
+        }
+
+        let source = Library::open_with(from, &config.data_dir)?;
+        let mut target = Library::open_with(self.to, &config.data_dir)?;
+        for paper in source.iter() {
+            target.add(paper.clone());
+        }
+        target.forget_history();
+        target.save()?;
+
+        println!(
+            "Copied {} papers from {:?} to {:?}.",
+            source.size(),
+            from,
+            self.to
+        );
+        if config.backend != self.to {
+            println!(
+                "Set `backend = \"{}\"` in your xivar.toml to use it.",
+                format!("{:?}", self.to).to_lowercase()
+            );
+        }
+        Ok(())
+    }
+}
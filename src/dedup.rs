@@ -0,0 +1,121 @@
+use std::io::Write;
+
+use anyhow::Result;
+use clap::Parser;
+use console::style;
+
+use crate::{
+    library::{Library, LocalPaper},
+    xiv_config::Config,
+    PaperInfo, Venue,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Find and merge duplicate papers in your library")]
+pub struct Dedup {
+    /// Only list the duplicates
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl Dedup {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let mut lib = Library::open(&config)?;
+
+        let clusters = lib.duplicates();
+        if clusters.is_empty() {
+            println!("No duplicates found.");
+            return Ok(());
+        }
+
+        let mut merged = 0;
+        for (k, cluster) in clusters.iter().enumerate() {
+            println!(
+                "{}",
+                style(format!("Duplicates {}/{}:", k + 1, clusters.len())).bold()
+            );
+            for (i, paper) in cluster.iter().enumerate() {
+                print_entry(i + 1, paper);
+            }
+            if self.dry_run {
+                println!();
+                continue;
+            }
+
+            let default = default_choice(cluster);
+            match ask(cluster.len(), default)? {
+                Choice::Keep(i) => {
+                    let infos: Vec<PaperInfo> =
+                        cluster.iter().map(|p| p.metadata().clone()).collect();
+                    let paper = lib.merge_duplicates(cluster[i].metadata(), &infos);
+                    println!("Merged into {}\n", paper);
+                    merged += 1;
+                }
+                Choice::Skip => println!(),
+                Choice::Quit => break,
+            }
+        }
+
+        if merged > 0 {
+            println!("Merged {} groups of duplicates.", merged);
+            lib.save()?;
+        }
+        Ok(())
+    }
+}
+
+fn print_entry(i: usize, paper: &LocalPaper) {
+    let info = paper.metadata();
+    println!("  ({}) {}", i, paper);
+    if let Some(id) = &info.id {
+        println!("      id: {}", id.to_prefixed_string());
+    }
+    for attachment in &paper.attachments {
+        println!("      {}: {}", attachment.role, attachment.path.display());
+    }
+}
+
+/// Proposes to keep the metadata of a published version over a preprint.
+fn default_choice(cluster: &[LocalPaper]) -> usize {
+    cluster
+        .iter()
+        .position(|p| !is_preprint(p.metadata()))
+        .unwrap_or(0)
+}
+
+fn is_preprint(info: &PaperInfo) -> bool {
+    match &info.venue {
+        Venue::Arxiv(_) => true,
+        Venue::Journal(name) | Venue::Conf(name) => name.eq_ignore_ascii_case("corr"),
+    }
+}
+
+enum Choice {
+    Keep(usize),
+    Skip,
+    Quit,
+}
+
+fn ask(n: usize, default: usize) -> Result<Choice> {
+    loop {
+        print!(
+            "Keep the metadata of [1-{}] (default {}), (s)kip or (q)uit: ",
+            n,
+            default + 1
+        );
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            return Ok(Choice::Quit);
+        }
+        match input.trim() {
+            "" => return Ok(Choice::Keep(default)),
+            "s" => return Ok(Choice::Skip),
+            "q" => return Ok(Choice::Quit),
+            s => match s.parse::<usize>() {
+                Ok(i) if i >= 1 && i <= n => return Ok(Choice::Keep(i - 1)),
+                _ => println!("Invalid choice!"),
+            },
+        }
+    }
+}
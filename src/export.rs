@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use crate::{
+    formats::{bibtex, csl, native, ris, Format},
+    library::{citekey, Library, LocalPaper},
+    template::Template,
+    xiv_config::Config,
+    Query,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Export your library to a human-readable file")]
+pub struct Export {
+    /// Output file, prints to stdout if omitted
+    output: Option<PathBuf>,
+
+    /// Output format, guessed from the file extension if omitted
+    #[clap(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// Only export the papers matching this query, e.g. `tag:thesis`
+    #[clap(short, long)]
+    query: Option<String>,
+
+    /// Scheme of the citation keys, e.g. `{author}{year}{veryshorttitle}`, defaults to the
+    /// `citekey` setting or else the citation keys of the library
+    #[clap(long, value_name = "TEMPLATE")]
+    citekey: Option<Template>,
+
+    /// Save the citation keys after `--citekey` in the library, so that they do not change in
+    /// later exports
+    #[clap(long)]
+    save_citekeys: bool,
+}
+
+impl Export {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let mut lib = Library::open(&config)?;
+        let template = self.citekey.as_ref().or(config.citekey.as_ref());
+        if self.save_citekeys {
+            match template {
+                Some(template) => lib.set_citekeys(template)?,
+                None => bail!("--save-citekeys needs a template, give one with --citekey"),
+            }
+        }
+        let mut papers: Vec<LocalPaper> = lib.iter().cloned().collect();
+        // keys are generated for the whole library, so that they do not depend on the query
+        if let Some(template) = template {
+            let keys = citekey::generate_all(&papers, template)?;
+            for (paper, key) in papers.iter_mut().zip(keys) {
+                paper.citekey = key;
+            }
+        }
+        if let Some(query) = &self.query {
+            let query = Query::from(query.to_owned());
+            papers.retain(|paper| paper.matches(&query));
+        }
+
+        let format = match (self.format, &self.output) {
+            (Some(format), _) => format,
+            (None, Some(output)) => Format::from_path(output)?,
+            (None, None) => Format::Json,
+        };
+        let content = match format {
+            Format::Bibtex => bibtex::write(&papers),
+            Format::Ris => ris::write(&papers)?,
+            Format::CslJson => csl::write(&papers)?,
+            format => native::write(format, &papers)?,
+        };
+
+        match &self.output {
+            Some(output) => {
+                std::fs::write(output, content)
+                    .with_context(|| format!("could not write to {}", output.display()))?;
+                println!("Exported {} papers to {:?}", papers.len(), output);
+            }
+            None => println!("{}", content),
+        }
+        lib.save()
+    }
+}
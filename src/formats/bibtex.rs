@@ -0,0 +1,862 @@
+//! BibTeX files, e.g. bibliographies managed by JabRef, which links the PDFs of its entries in
+//! `file` fields.
+//!
+//! Entries are mapped to papers as follows:
+//!
+//! | field                          | paper                                              |
+//! |--------------------------------|----------------------------------------------------|
+//! | key                            | `citekey`, if it is a valid one                    |
+//! | `title`                        | `title`, entries without a title are not imported  |
+//! | `author`, or else `editor`     | `authors`                                          |
+//! | `year`, or else `date`         | `year`                                             |
+//! | `journal`, `booktitle`         | journal or conference venue, arXiv for `CoRR`      |
+//! | `howpublished` of arXiv papers | arXiv venue                                        |
+//! | `doi`, or else arXiv `eprint`  | `id`                                               |
+//! | `abstract`                     | `summary`                                          |
+//! | `url`, `doi`                   | `ees`                                              |
+//! | `keywords`                     | `tags`                                             |
+//! | `annote`, or else `note`       | `note`, line by line                               |
+//! | `file`                         | `attachments`, only PDFs                           |
+//!
+//! `xivar export` writes the same fields.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use unicode_normalization::UnicodeNormalization;
+
+use super::{is_arxiv_venue, resolve_path, Bibliography};
+use crate::{
+    library::{citekey, Attachment, AttachmentRole, LocalPaper},
+    ArxivIdentifier, Doi, Identifier, PaperInfo, PaperTitle, PaperUrl, Venue,
+};
+
+/// A BibTeX entry with lowercase type and field names. Field values are still LaTeX.
+#[derive(Debug)]
+struct Entry {
+    kind: String,
+    key: String,
+    fields: HashMap<String, String>,
+}
+
+impl Entry {
+    fn get(&self, field: &str) -> Option<&str> {
+        self.fields
+            .get(field)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn text(&self, field: &str) -> Option<String> {
+        self.get(field)
+            .map(latex_to_text)
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Reads the entries of the BibTeX file with `content`. Relative paths of linked files are
+/// resolved against `base_dir`, the absolute path of the directory of the file.
+pub fn read(content: &str, base_dir: &Path) -> Bibliography {
+    let mut bib = Bibliography::default();
+    for entry in Parser::new(content).entries(&mut bib.unmapped) {
+        match to_paper(&entry, base_dir, &mut bib.missing) {
+            Ok(paper) => bib.papers.push(paper),
+            Err(err) => bib.unmapped.push((entry.key, err.to_string())),
+        }
+    }
+    bib
+}
+
+/// Writes an entry for every paper with its citation key. Journal papers are written as
+/// `@article`, conference papers as `@inproceedings` and arXiv preprints as `@misc` with their
+/// `eprint`. Files are linked as JabRef does, with their role as description.
+pub fn write(papers: &[LocalPaper]) -> String {
+    let mut out = String::new();
+    for paper in papers {
+        let info = paper.metadata();
+        let mut fields: Vec<(&str, String)> = vec![];
+        fields.push(("title", escape(&info.title.to_string())));
+        if !info.authors.is_empty() {
+            let authors: Vec<String> = info.authors.iter().map(|a| escape(a)).collect();
+            fields.push(("author", authors.join(" and ")));
+        }
+        let (kind, venue_field) = match &info.venue {
+            Venue::Journal(_) => ("article", "journal"),
+            Venue::Conf(_) => ("inproceedings", "booktitle"),
+            Venue::Arxiv(_) => ("misc", "howpublished"),
+        };
+        let venue = info.venue.to_string();
+        if !venue.is_empty() {
+            fields.push((venue_field, escape(&venue)));
+        }
+        if !info.year.is_empty() {
+            fields.push(("year", escape(&info.year)));
+        }
+        match &info.id {
+            Some(Identifier::Doi(doi)) => fields.push(("doi", doi.to_string())),
+            Some(Identifier::Arxiv(arxiv)) => fields.push(("eprint", arxiv.to_string())),
+            Some(Identifier::Custom(_)) | None => {}
+        }
+        if matches!(info.venue, Venue::Arxiv(_)) || matches!(info.id, Some(Identifier::Arxiv(_))) {
+            fields.push(("archiveprefix", "arXiv".to_owned()));
+        }
+        if let Some(url) = paper.ees.first() {
+            fields.push(("url", escape_url(&url.raw())));
+        }
+        if let Some(summary) = &info.summary {
+            fields.push(("abstract", escape(summary)));
+        }
+        if !paper.tags.is_empty() {
+            let tags: Vec<String> = paper.tags.iter().map(|t| escape(t)).collect();
+            fields.push(("keywords", tags.join(", ")));
+        }
+        if !paper.attachments.is_empty() {
+            let files: Vec<String> = paper
+                .attachments
+                .iter()
+                .map(|a| {
+                    let path = a.path.to_string_lossy();
+                    format!("{}:{}:PDF", a.role, escape_link(&path))
+                })
+                .collect();
+            fields.push(("file", files.join(";")));
+        }
+        if !paper.note.trim().is_empty() {
+            // line breaks are kept, as they matter in Markdown
+            let lines: Vec<String> = paper.note.trim().lines().map(escape).collect();
+            fields.push(("annote", lines.join("\n")));
+        }
+
+        out.push_str(&format!("@{}{{{},\n", kind, paper.citekey));
+        for (name, value) in fields {
+            out.push_str(&format!("  {} = {{{}}},\n", name, value));
+        }
+        out.push_str("}\n\n");
+    }
+    out
+}
+
+/// Escapes the characters which are special in LaTeX text.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+    {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes the characters of URLs which LaTeX reads as comment or macro parameter, as the `url`
+/// and `hyperref` packages expect.
+fn escape_url(url: &str) -> String {
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars() {
+        if matches!(c, '%' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Inverse of `escape_url`, which also drops the escapes of `&`, `_` and `~`.
+fn unescape_url(url: &str) -> String {
+    let mut unescaped = String::with_capacity(url.len());
+    let mut chars = url.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('%' | '#' | '&' | '_' | '~')) => {}
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Escapes the separators of `file` fields as JabRef does.
+fn escape_link(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '\\' | ':' | ';') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn to_paper(entry: &Entry, base_dir: &Path, missing: &mut Vec<PathBuf>) -> Result<LocalPaper> {
+    let title = entry.text("title").ok_or_else(|| anyhow!("no title"))?;
+    let authors = entry
+        .get("author")
+        .or_else(|| entry.get("editor"))
+        .map(split_names)
+        .unwrap_or_default();
+    let year = entry
+        .get("year")
+        .or_else(|| entry.get("date").and_then(|date| date.get(..4)))
+        .map(latex_to_text)
+        .unwrap_or_default();
+
+    let is_arxiv = ["eprinttype", "archiveprefix"].iter().any(|field| {
+        entry
+            .get(field)
+            .is_some_and(|v| v.eq_ignore_ascii_case("arxiv"))
+    });
+    let journal = entry.text("journal").or_else(|| entry.text("journaltitle"));
+    let venue = match (journal, entry.text("booktitle")) {
+        (Some(journal), _) if is_arxiv_venue(&journal) => Venue::Arxiv(journal),
+        (Some(journal), _) => Venue::Journal(journal),
+        (None, Some(booktitle)) => Venue::Conf(booktitle),
+        (None, None) if is_arxiv => Venue::Arxiv(
+            entry
+                .text("howpublished")
+                .unwrap_or_else(|| "arXiv".to_owned()),
+        ),
+        (None, None) if entry.kind == "article" => Venue::Journal(String::new()),
+        (None, None) => Venue::Conf(
+            ["publisher", "school", "institution", "howpublished"]
+                .iter()
+                .find_map(|field| entry.text(field))
+                .unwrap_or_default(),
+        ),
+    };
+
+    let doi = entry.get("doi").and_then(|doi| {
+        let doi = [
+            "https://doi.org/",
+            "http://dx.doi.org/",
+            "https://dx.doi.org/",
+            "doi:",
+        ]
+        .iter()
+        .fold(doi, |doi, prefix| doi.strip_prefix(prefix).unwrap_or(doi));
+        Doi::parse_doi(doi).ok()
+    });
+    let arxiv = entry
+        .get("eprint")
+        .filter(|_| is_arxiv || matches!(venue, Venue::Arxiv(_)))
+        .and_then(|eprint| ArxivIdentifier::parse_string(eprint).ok());
+    let id = match (&doi, arxiv) {
+        (Some(doi), _) => Some(Identifier::Doi(doi.clone())),
+        (None, Some(arxiv)) => Some(Identifier::Arxiv(arxiv)),
+        (None, None) => None,
+    };
+
+    let mut ees = vec![];
+    if let Some(url) = entry.get("url") {
+        ees.push(PaperUrl::new(unescape_url(url)));
+    }
+    if let Some(doi) = doi {
+        ees.push(PaperUrl::new(format!("https://doi.org/{}", doi)));
+    }
+
+    let mut attachments: Vec<Attachment> = vec![];
+    for (description, path) in entry.get("file").map(linked_pdfs).unwrap_or_default() {
+        let path = resolve_path(base_dir, &path);
+        if !path.is_file() {
+            missing.push(path);
+        } else if !attachments.iter().any(|a| a.path == path) {
+            // xivar writes the role as description
+            let role = match description.parse() {
+                Ok(role) => role,
+                Err(_) if attachments.is_empty() => AttachmentRole::Main,
+                Err(_) => AttachmentRole::Supplement,
+            };
+            attachments.push(Attachment::new(path, role));
+        }
+    }
+
+    let metadata = PaperInfo {
+        id,
+        title: PaperTitle::new(title),
+        venue,
+        authors,
+        year,
+        summary: entry.text("abstract"),
+    };
+    let mut paper = LocalPaper::new(metadata, attachments, ees);
+    if citekey::is_valid(&entry.key) {
+        paper.citekey = entry.key.clone();
+    }
+    paper.tags = entry
+        .text("keywords")
+        .map(|keywords| {
+            keywords
+                .split([',', ';'])
+                .map(|tag| tag.trim().to_owned())
+                .filter(|tag| !tag.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    paper.note = entry
+        .get("annote")
+        .or_else(|| entry.get("note"))
+        .map(|note| {
+            let lines: Vec<String> = note.lines().map(latex_to_text).collect();
+            lines.join("\n")
+        })
+        .unwrap_or_default();
+    Ok(paper)
+}
+
+/// Splits a list of names separated by `and` and brings each name into the form
+/// `First von Last`.
+fn split_names(names: &str) -> Vec<String> {
+    let names = names.split_whitespace().collect::<Vec<_>>().join(" ");
+    split_top_level(&names, " and ")
+        .into_iter()
+        .filter(|name| !name.trim().eq_ignore_ascii_case("others"))
+        .map(|name| {
+            let parts = split_top_level(&name, ",");
+            let name = match parts.as_slice() {
+                [last, first] => format!("{} {}", first.trim(), last.trim()),
+                [last, jr, first] => format!("{} {} {}", first.trim(), last.trim(), jr.trim()),
+                _ => name,
+            };
+            latex_to_text(&name)
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Splits `value` at the ASCII case-insensitive `separator` outside of braces.
+fn split_top_level(value: &str, separator: &str) -> Vec<String> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    let mut i = 0;
+    while i < value.len() {
+        match value.as_bytes()[i] {
+            b'{' => depth += 1,
+            b'}' => depth -= 1,
+            _ if depth == 0
+                && value
+                    .get(i..i + separator.len())
+                    .is_some_and(|s| s.eq_ignore_ascii_case(separator)) =>
+            {
+                parts.push(value[start..i].to_owned());
+                i += separator.len();
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(value[start..].to_owned());
+    parts
+}
+
+/// Descriptions and paths of the PDFs in a `file` field, which is either a plain path or a list
+/// of `description:path:type` separated by `;` with `\` escaping `:`, `;` and `\` as written by
+/// JabRef.
+fn linked_pdfs(field: &str) -> Vec<(String, String)> {
+    let mut links = vec![vec![String::new()]];
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        let link = links.last_mut().unwrap();
+        match c {
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    link.last_mut().unwrap().push(c);
+                }
+            }
+            ':' => link.push(String::new()),
+            ';' => links.push(vec![String::new()]),
+            c => link.last_mut().unwrap().push(c),
+        }
+    }
+
+    links
+        .into_iter()
+        .filter_map(|parts| {
+            let (description, path, kind) = match parts.as_slice() {
+                [description, path @ .., kind] if parts.len() >= 3 => {
+                    (description.clone(), path.join(":"), kind.as_str())
+                }
+                // a plain path, possibly with a drive letter like in `C:/papers/paper.pdf`
+                _ => (String::new(), parts.join(":"), ""),
+            };
+            let path = path.trim().to_owned();
+            let is_pdf = kind.eq_ignore_ascii_case("pdf")
+                || Path::new(&path)
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+            Some((description, path)).filter(|(_, path)| is_pdf && !path.is_empty())
+        })
+        .collect()
+}
+
+/// Converts a LaTeX string to plain text: resolves accents and special characters, and drops
+/// braces, math delimiters and other commands.
+fn latex_to_text(latex: &str) -> String {
+    let mut text = String::new();
+    let mut chars = latex.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let mut command = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_alphabetic() {
+                        command.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if command.is_empty() {
+                    match chars.next() {
+                        Some(accent @ ('"' | '\'' | '`' | '^' | '~' | '=' | '.')) => {
+                            text.push_str(&accented(accent, &mut chars))
+                        }
+                        Some('\\') => text.push(' '),
+                        Some(c) => text.push(c),
+                        None => {}
+                    }
+                    continue;
+                }
+                // a space after a command name only ends the name
+                if chars.peek() == Some(&' ') {
+                    chars.next();
+                }
+                match command.as_str() {
+                    "c" => text.push_str(&accented(',', &mut chars)),
+                    "v" | "u" | "H" | "k" | "r" => {
+                        let accent = command.chars().next().unwrap();
+                        text.push_str(&accented(accent, &mut chars))
+                    }
+                    "ss" => text.push('ß'),
+                    "o" => text.push('ø'),
+                    "O" => text.push('Ø'),
+                    "ae" => text.push('æ'),
+                    "AE" => text.push('Æ'),
+                    "oe" => text.push('œ'),
+                    "OE" => text.push('Œ'),
+                    "aa" => text.push('å'),
+                    "AA" => text.push('Å'),
+                    "l" => text.push('ł'),
+                    "L" => text.push('Ł'),
+                    "i" => text.push('i'),
+                    "j" => text.push('j'),
+                    "textbackslash" => text.push('\\'),
+                    "textasciicircum" => text.push('^'),
+                    "textasciitilde" => text.push('~'),
+                    "textendash" => text.push('–'),
+                    "textemdash" => text.push('—'),
+                    // formatting commands like \emph, whose arguments are kept
+                    _ => {}
+                }
+            }
+            '{' | '}' | '$' => {}
+            '~' => text.push(' '),
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                if chars.peek() == Some(&'-') {
+                    chars.next();
+                    text.push('—');
+                } else {
+                    text.push('–');
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .nfc()
+        .collect()
+}
+
+/// The argument of an accent command, i.e. a braced group or a single character, with the
+/// accent as combining character.
+fn accented(accent: char, chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mark = match accent {
+        '"' => '\u{308}',
+        '\'' => '\u{301}',
+        '`' => '\u{300}',
+        '^' => '\u{302}',
+        '~' => '\u{303}',
+        '=' => '\u{304}',
+        '.' => '\u{307}',
+        ',' => '\u{327}',
+        'v' => '\u{30c}',
+        'u' => '\u{306}',
+        'H' => '\u{30b}',
+        'k' => '\u{328}',
+        _ => '\u{30a}',
+    };
+    while chars.peek() == Some(&' ') {
+        chars.next();
+    }
+    let argument = if chars.peek() == Some(&'{') {
+        chars.next();
+        let mut argument = String::new();
+        for c in chars.by_ref() {
+            if c == '}' {
+                break;
+            }
+            argument.push(c);
+        }
+        latex_to_text(&argument)
+    } else {
+        chars.next().map(String::from).unwrap_or_default()
+    };
+    let mut argument = argument.chars();
+    match argument.next() {
+        Some(first) => std::iter::once(first)
+            .chain(std::iter::once(mark))
+            .chain(argument)
+            .collect(),
+        None => String::new(),
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    /// Abbreviations defined with `@string`.
+    strings: HashMap<String, String>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        let strings = [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ]
+        .iter()
+        .map(|month| (month[..3].to_lowercase(), month.to_string()))
+        .collect();
+        Parser {
+            src,
+            pos: 0,
+            strings,
+        }
+    }
+
+    /// All entries, skipping comments and recording entries which cannot be parsed in `failed`.
+    fn entries(&mut self, failed: &mut Vec<(String, String)>) -> Vec<Entry> {
+        let mut entries = vec![];
+        while let Some(start) = self.src[self.pos..].find('@') {
+            self.pos += start + 1;
+            let entry_start = self.pos;
+            let kind = self.identifier().to_lowercase();
+            // text outside of entries is a comment, e.g. an email address
+            self.skip_whitespace();
+            if kind.is_empty() || !matches!(self.peek(), Some('{' | '(')) {
+                continue;
+            }
+            let result = match kind.as_str() {
+                "comment" | "preamble" => self.skip_block().map(|_| None),
+                "string" => self.string().map(|_| None),
+                _ => self.entry(kind).map(Some),
+            };
+            match result {
+                Ok(Some(entry)) => entries.push(entry),
+                Ok(None) => {}
+                Err(err) => {
+                    let line = self.src[..entry_start].lines().count();
+                    failed.push((format!("entry in line {}", line), err.to_string()));
+                    // continue with the next entry at the start of a line
+                    self.pos = self.src[entry_start..]
+                        .find("\n@")
+                        .map_or(self.src.len(), |next| entry_start + next);
+                }
+            }
+        }
+        entries
+    }
+
+    fn entry(&mut self, kind: String) -> Result<Entry> {
+        let close = self.open()?;
+        self.skip_whitespace();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c != ',' && c != close && !c.is_whitespace())
+        {
+            self.bump();
+        }
+        let key = self.src[start..self.pos].to_owned();
+        let mut fields = HashMap::new();
+        loop {
+            self.skip_whitespace();
+            match self.bump() {
+                Some(',') => {}
+                Some(c) if c == close => break,
+                _ => bail!("expected `,` or `{}` after the field of {}", close, key),
+            }
+            self.skip_whitespace();
+            if self.peek() == Some(close) {
+                self.bump();
+                break;
+            }
+            let name = self.identifier().to_lowercase();
+            if name.is_empty() {
+                bail!("expected a field name in {}", key);
+            }
+            self.expect('=')?;
+            let value = self.value()?;
+            fields.insert(name, value);
+        }
+        Ok(Entry { kind, key, fields })
+    }
+
+    fn string(&mut self) -> Result<()> {
+        let close = self.open()?;
+        self.skip_whitespace();
+        let name = self.identifier().to_lowercase();
+        self.expect('=')?;
+        let value = self.value()?;
+        self.strings.insert(name, value);
+        self.skip_whitespace();
+        if self.bump() != Some(close) {
+            bail!("expected `{}` after @string", close);
+        }
+        Ok(())
+    }
+
+    /// A value consisting of braced or quoted strings, numbers and abbreviations joined by `#`.
+    fn value(&mut self) -> Result<String> {
+        let mut value = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') => {
+                    self.bump();
+                    value.push_str(self.until_balanced('}')?);
+                }
+                Some('"') => {
+                    self.bump();
+                    value.push_str(self.until_balanced('"')?);
+                }
+                Some(c) if c.is_ascii_digit() => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                        self.bump();
+                    }
+                    value.push_str(&self.src[start..self.pos]);
+                }
+                _ => {
+                    let name = self.identifier().to_lowercase();
+                    if name.is_empty() {
+                        bail!("expected a value");
+                    }
+                    // undefined abbreviations are kept as they are
+                    value.push_str(self.strings.get(&name).map_or(&name, |v| v));
+                }
+            }
+            self.skip_whitespace();
+            if self.peek() == Some('#') {
+                self.bump();
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// The text up to the `end` outside of nested braces, which is consumed.
+    fn until_balanced(&mut self, end: char) -> Result<&'a str> {
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '{' => depth += 1,
+                c if c == end && depth == 0 => return Ok(&self.src[start..self.pos - 1]),
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        bail!("unbalanced braces")
+    }
+
+    fn skip_block(&mut self) -> Result<()> {
+        let close = self.open()?;
+        self.until_balanced(close).map(|_| ())
+    }
+
+    /// Consumes the opening delimiter of an entry and returns the closing one.
+    fn open(&mut self) -> Result<char> {
+        self.skip_whitespace();
+        match self.bump() {
+            Some('{') => Ok('}'),
+            Some('(') => Ok(')'),
+            _ => bail!("expected `{{` or `(`"),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            _ => bail!("expected `{}`", expected),
+        }
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || "_-:.+/'".contains(c))
+        {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_characters_round_trip() {
+        let info = PaperInfo {
+            id: None,
+            title: PaperTitle::new("Paths in C:\\temp, x^2 & ~50% of $n_i$ {sets}".to_owned()),
+            venue: Venue::Journal("J. Algorithms #3".to_owned()),
+            authors: vec!["Jürgen Müller".to_owned()],
+            year: "2020".to_owned(),
+            summary: Some("A ~ B \\ C ^ D".to_owned()),
+        };
+        let paper = LocalPaper::new(info, vec![], vec![]);
+        let bib = read(&write(std::slice::from_ref(&paper)), Path::new("/"));
+        assert!(bib.unmapped.is_empty());
+        let read = bib.papers[0].metadata();
+        assert_eq!(read.title.to_string(), paper.metadata().title.to_string());
+        assert_eq!(read.venue, paper.metadata().venue);
+        assert_eq!(read.authors, paper.metadata().authors);
+        assert_eq!(read.summary, paper.metadata().summary);
+    }
+
+    #[test]
+    fn text_outside_entries_is_skipped() {
+        let content = "Maintained by someone@example.org, mail me @ any time.
+
+@article{megow2022,
+  title = {Scheduling},
+  year = 2022,
+}
+
+@misc{broken,
+  title = {Unterminated
+";
+        let bib = read(content, Path::new("/"));
+        assert_eq!(bib.papers.len(), 1);
+        assert_eq!(bib.papers[0].citekey, "megow2022");
+        assert_eq!(bib.unmapped.len(), 1);
+    }
+
+    #[test]
+    fn url_round_trip() {
+        let url = "https://example.org/paper.pdf?id=50%25#page=3";
+        let info = PaperInfo {
+            id: None,
+            title: PaperTitle::new("Scheduling".to_owned()),
+            venue: Venue::Journal("J. Algorithms".to_owned()),
+            authors: vec![],
+            year: "2020".to_owned(),
+            summary: None,
+        };
+        let paper = LocalPaper::new(info, vec![], vec![PaperUrl::new(url.to_owned())]);
+        let content = write(std::slice::from_ref(&paper));
+        assert!(content.contains("50\\%25\\#page"));
+        let bib = read(&content, Path::new("/"));
+        assert_eq!(bib.papers[0].ees[0].raw(), url);
+    }
+
+    #[test]
+    fn invalid_keys_are_replaced() {
+        let content = "@article{Megow:2022/1,\n  title = {Scheduling},\n  author = {Megow, Nicole},\n  year = 2022,\n}\n";
+        let bib = read(content, Path::new("/"));
+        assert_eq!(bib.papers[0].citekey, "megow2022scheduling");
+    }
+
+    #[test]
+    fn linked_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("papers")).unwrap();
+        for name in ["papers/main.pdf", "talk;v2:final.pdf", "notes.txt"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let content = format!(
+            "@article{{megow2022,
+  title = {{Scheduling}},
+  file = {{:papers/main.pdf:PDF;slides:talk\\;v2\\:final.pdf:PDF;:notes.txt:Text;:{}:PDF;:gone.pdf:PDF}},
+}}
+",
+            escape_link(&dir.path().join("papers/main.pdf").to_string_lossy())
+        );
+        let bib = read(&content, dir.path());
+        let paper = &bib.papers[0];
+        let files: Vec<(&Path, AttachmentRole)> = paper
+            .attachments
+            .iter()
+            .map(|a| (a.path.as_path(), a.role))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                (
+                    dir.path().join("papers/main.pdf").as_path(),
+                    AttachmentRole::Main
+                ),
+                (
+                    dir.path().join("talk;v2:final.pdf").as_path(),
+                    AttachmentRole::Slides
+                ),
+            ]
+        );
+        assert_eq!(bib.missing, [dir.path().join("gone.pdf")]);
+
+        // and back
+        let bib = read(&write(std::slice::from_ref(paper)), Path::new("/"));
+        assert_eq!(bib.papers[0].attachments, paper.attachments);
+    }
+}
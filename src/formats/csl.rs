@@ -0,0 +1,302 @@
+//! CSL-JSON, the format of citeproc, which many reference managers like Zotero read and write,
+//! and doi.org serves.
+//!
+//! A file is a list of items. Journal papers are written as `article-journal`, conference papers
+//! as `paper-conference` and arXiv preprints as `article` with their identifier in `number`,
+//! e.g. `arXiv:2101.01234`. Notes are kept in `note`, and the attachments of a paper and custom
+//! identifiers, for which CSL-JSON has no fields, in `custom`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+
+use super::{is_arxiv_venue, resolve_path, Bibliography};
+use crate::{
+    library::{Attachment, AttachmentRole, LocalPaper},
+    ArxivIdentifier, Doi, Identifier, PaperInfo, PaperTitle, PaperUrl, Venue,
+};
+
+pub fn write(papers: &[LocalPaper]) -> Result<String> {
+    let items: Vec<Value> = papers.iter().map(to_item).collect();
+    Ok(serde_json::to_string_pretty(&items)?)
+}
+
+/// Reads the items of the CSL-JSON file with `content`. Relative paths of attachments are
+/// resolved against `base_dir`, the absolute path of the directory of the file.
+pub fn read(content: &str, base_dir: &Path) -> Result<Bibliography> {
+    let items = match serde_json::from_str(content)? {
+        Value::Array(items) => items,
+        // a single item, as served by doi.org
+        item @ Value::Object(_) => vec![item],
+        _ => bail!("expected a list of CSL-JSON items"),
+    };
+    let mut bib = Bibliography::default();
+    for (i, item) in items.iter().enumerate() {
+        match to_paper(item, base_dir, &mut bib.missing) {
+            Ok(paper) => bib.papers.push(paper),
+            Err(err) => {
+                let key = match &item["id"] {
+                    Value::String(id) => id.to_owned(),
+                    Value::Number(id) => id.to_string(),
+                    _ => format!("item {}", i + 1),
+                };
+                bib.unmapped.push((key, err.to_string()));
+            }
+        }
+    }
+    Ok(bib)
+}
+
+fn to_item(paper: &LocalPaper) -> Value {
+    let info = paper.metadata();
+    let mut item = Map::new();
+    item.insert("id".to_owned(), json!(paper.citekey));
+    item.insert("citation-key".to_owned(), json!(paper.citekey));
+    let (kind, venue) = match &info.venue {
+        Venue::Journal(name) => ("article-journal", name),
+        Venue::Conf(name) => ("paper-conference", name),
+        Venue::Arxiv(name) => ("article", name),
+    };
+    item.insert("type".to_owned(), json!(kind));
+    item.insert("title".to_owned(), json!(info.title.to_string()));
+    let authors: Vec<Value> = info
+        .authors
+        .iter()
+        .map(|author| match author.rsplit_once(' ') {
+            Some((given, family)) => json!({ "given": given, "family": family }),
+            None => json!({ "literal": author }),
+        })
+        .collect();
+    item.insert("author".to_owned(), Value::Array(authors));
+    match info.year.parse::<i64>() {
+        Ok(year) => item.insert("issued".to_owned(), json!({ "date-parts": [[year]] })),
+        Err(_) if !info.year.is_empty() => {
+            item.insert("issued".to_owned(), json!({ "literal": info.year }))
+        }
+        Err(_) => None,
+    };
+    if !venue.is_empty() {
+        item.insert("container-title".to_owned(), json!(venue));
+    }
+
+    let mut custom = Map::new();
+    match &info.id {
+        Some(Identifier::Doi(doi)) => {
+            item.insert("DOI".to_owned(), json!(doi.to_string()));
+        }
+        Some(Identifier::Arxiv(arxiv)) => {
+            item.insert("number".to_owned(), json!(format!("arXiv:{}", arxiv)));
+        }
+        Some(Identifier::Custom(id)) => {
+            custom.insert("id".to_owned(), json!(id));
+        }
+        None => {}
+    }
+    if let Some(url) = paper.ees.first() {
+        item.insert("URL".to_owned(), json!(url.raw()));
+    }
+    if let Some(summary) = &info.summary {
+        item.insert("abstract".to_owned(), json!(summary));
+    }
+    if !paper.tags.is_empty() {
+        item.insert("keyword".to_owned(), json!(paper.tags.join(", ")));
+    }
+    if !paper.note.is_empty() {
+        item.insert("note".to_owned(), json!(paper.note));
+    }
+    if !paper.attachments.is_empty() {
+        let attachments: Vec<Value> = paper
+            .attachments
+            .iter()
+            .map(|a| json!({ "path": a.path, "role": a.role.to_string() }))
+            .collect();
+        custom.insert("attachments".to_owned(), Value::Array(attachments));
+    }
+    if !custom.is_empty() {
+        item.insert("custom".to_owned(), Value::Object(custom));
+    }
+    Value::Object(item)
+}
+
+fn to_paper(item: &Value, base_dir: &Path, missing: &mut Vec<PathBuf>) -> Result<LocalPaper> {
+    let info = to_info(item).ok_or_else(|| anyhow!("no title"))?;
+    let mut attachments = vec![];
+    for attachment in item["custom"]["attachments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        let path = match attachment["path"].as_str() {
+            Some(path) => resolve_path(base_dir, path),
+            None => continue,
+        };
+        if !path.is_file() {
+            missing.push(path);
+            continue;
+        }
+        let role = attachment["role"]
+            .as_str()
+            .and_then(|role| role.parse().ok())
+            .unwrap_or(AttachmentRole::Main);
+        attachments.push(Attachment::new(path, role));
+    }
+    let ees = item["URL"]
+        .as_str()
+        .map(|url| vec![PaperUrl::new(url.to_owned())])
+        .unwrap_or_default();
+
+    let mut paper = LocalPaper::new(info, attachments, ees);
+    if let Some(key) = item["citation-key"].as_str().or(item["id"].as_str()) {
+        paper.citekey = key.to_owned();
+    }
+    paper.tags = item["keyword"]
+        .as_str()
+        .map(|keywords| {
+            keywords
+                .split([',', ';'])
+                .map(|tag| tag.trim().to_owned())
+                .filter(|tag| !tag.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    paper.note = item["note"].as_str().unwrap_or_default().to_owned();
+    Ok(paper)
+}
+
+/// The metadata of a CSL-JSON item.
+pub fn to_info(item: &Value) -> Option<PaperInfo> {
+    // some registries give lists of titles
+    let text = |value: &Value| match value {
+        Value::Array(values) => values.first().and_then(Value::as_str).map(str::to_owned),
+        value => value.as_str().map(str::to_owned),
+    };
+    let title = text(&item["title"]).filter(|title| !title.trim().is_empty())?;
+    let authors = item["author"]
+        .as_array()
+        .map(|authors| authors.iter().filter_map(name).collect())
+        .unwrap_or_default();
+    let year = match item["issued"]["date-parts"][0][0].as_i64() {
+        Some(year) => year.to_string(),
+        None => item["issued"]["date-parts"][0][0]
+            .as_str()
+            .or(item["issued"]["literal"].as_str())
+            .or(item["issued"]["raw"].as_str())
+            .unwrap_or_default()
+            .to_owned(),
+    };
+
+    let container = text(&item["container-title"]).unwrap_or_default();
+    let venue = match item["type"].as_str() {
+        Some("article-journal") if is_arxiv_venue(&container) => Venue::Arxiv(container),
+        Some("article-journal") => Venue::Journal(container),
+        Some("article" | "posted-content") => Venue::Arxiv(container),
+        _ => Venue::Conf(container),
+    };
+
+    let arxiv = item["number"]
+        .as_str()
+        .and_then(|number| number.strip_prefix("arXiv:"))
+        .and_then(|id| ArxivIdentifier::parse_string(id).ok());
+    let id = match (item["DOI"].as_str(), arxiv) {
+        (Some(doi), _) => Doi::parse_doi(doi).ok().map(Identifier::Doi),
+        (None, Some(arxiv)) => Some(Identifier::Arxiv(arxiv)),
+        (None, None) => item["custom"]["id"]
+            .as_str()
+            .map(|id| Identifier::Custom(id.to_owned())),
+    };
+
+    Some(PaperInfo {
+        id,
+        title: PaperTitle::new(title),
+        venue,
+        authors,
+        year,
+        summary: item["abstract"].as_str().map(str::to_owned),
+    })
+}
+
+/// The name of a CSL-JSON name variable, e.g. `{"given": "Nicole", "family": "Megow"}`.
+fn name(name: &Value) -> Option<String> {
+    if let Some(literal) = name["literal"].as_str() {
+        return Some(literal.to_owned());
+    }
+    let parts: Vec<&str> = [
+        "given",
+        "dropping-particle",
+        "non-dropping-particle",
+        "family",
+        "suffix",
+    ]
+    .iter()
+    .filter_map(|part| name[part].as_str())
+    .filter(|part| !part.is_empty())
+    .collect();
+    Some(parts.join(" ")).filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/formats/fixtures")
+    }
+
+    fn sample() -> Bibliography {
+        let content = std::fs::read_to_string(fixtures().join("sample.csl.json")).unwrap();
+        read(&content, &fixtures()).unwrap()
+    }
+
+    #[test]
+    fn read_sample() {
+        let bib = sample();
+        assert_eq!(bib.papers.len(), 2);
+        assert_eq!(bib.unmapped, [("3".to_owned(), "no title".to_owned())]);
+        assert_eq!(
+            bib.missing,
+            [PathBuf::from("/nonexistent/im2022-slides.pdf")]
+        );
+
+        let paper = &bib.papers[0];
+        let info = paper.metadata();
+        assert_eq!(paper.citekey, "im2022nonclairvoyant");
+        assert_eq!(info.authors, ["Sungjin Im", "Ravi Kumar"]);
+        assert_eq!(info.year, "2022");
+        assert_eq!(info.venue, Venue::Conf("STOC".to_owned()));
+        assert_eq!(
+            info.id.as_ref().map(Identifier::to_prefixed_string),
+            Some("doi:10.1145/3519935.3520010".to_owned())
+        );
+        assert_eq!(paper.tags, ["scheduling", "predictions"]);
+        assert_eq!(paper.attachments.len(), 1);
+        assert_eq!(paper.attachments[0].path, fixtures().join("paper.pdf"));
+        assert_eq!(
+            paper.note,
+            "Compare with the clairvoyant bounds.\n\n- Section 3 has the main algorithm."
+        );
+
+        let preprint = bib.papers[1].metadata();
+        assert_eq!(preprint.venue, Venue::Arxiv("CoRR".to_owned()));
+        assert_eq!(
+            preprint.id.as_ref().map(Identifier::to_prefixed_string),
+            Some("arxiv:2202.10199".to_owned())
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let papers = sample().papers;
+        let bib = read(&write(&papers).unwrap(), Path::new("/")).unwrap();
+        assert!(bib.unmapped.is_empty() && bib.missing.is_empty());
+        assert_eq!(bib.papers.len(), papers.len());
+        for (read, written) in bib.papers.iter().zip(&papers) {
+            assert_eq!(read.metadata().title, written.metadata().title);
+            assert_eq!(read.metadata().authors, written.metadata().authors);
+            assert_eq!(read.metadata().venue, written.metadata().venue);
+            assert_eq!(read.metadata().year, written.metadata().year);
+            assert_eq!(read.metadata().summary, written.metadata().summary);
+            assert_eq!(read, written);
+        }
+    }
+}
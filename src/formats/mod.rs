@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+
+use crate::library::LocalPaper;
+
+pub mod bibtex;
+pub mod csl;
+pub mod native;
+pub mod ris;
+#[cfg(feature = "sqlite")]
+pub mod zotero;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Bibtex,
+    Ris,
+    CslJson,
+    /// A copy of the `zotero.sqlite` database of Zotero, only for import
+    Zotero,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Format> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if name.to_lowercase().ends_with(".csl.json") {
+            return Ok(Format::CslJson);
+        }
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("json") => Ok(Format::Json),
+            Some("toml") => Ok(Format::Toml),
+            Some("bib") => Ok(Format::Bibtex),
+            Some("ris") => Ok(Format::Ris),
+            Some("sqlite") => Ok(Format::Zotero),
+            _ => bail!(
+                "Cannot guess format of {}, please specify one with --format",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Papers read from a BibTeX, RIS or CSL-JSON file.
+#[derive(Debug, Default)]
+pub struct Bibliography {
+    pub papers: Vec<LocalPaper>,
+    /// Keys of the entries which could not be mapped to papers, with the reason.
+    pub unmapped: Vec<(String, String)>,
+    /// Linked files which do not exist.
+    pub missing: Vec<PathBuf>,
+}
+
+/// Resolves a relative path against `base_dir`. Mendeley drops the leading `/` of absolute
+/// paths, which are tried as well.
+fn resolve_path(base_dir: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let relative = base_dir.join(path);
+    let absolute = Path::new("/").join(path);
+    if !relative.exists() && absolute.exists() {
+        absolute
+    } else {
+        relative
+    }
+}
+
+/// Whether a journal is arXiv, as DBLP calls it `CoRR`.
+fn is_arxiv_venue(journal: &str) -> bool {
+    let journal = journal.to_lowercase();
+    journal == "corr" || journal.starts_with("arxiv")
+}
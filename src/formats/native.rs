@@ -0,0 +1,275 @@
+//! Human-readable representation of the whole library, written by `xivar export`
+//! and read by `xivar import`.
+//!
+//! The file consists of a `version` and a list of `papers`. Every paper has the fields
+//!
+//! | field         | content                                                  |
+//! |---------------|----------------------------------------------------------|
+//! | `citekey`     | optional citation key, unique within the library         |
+//! | `title`       | title of the paper                                       |
+//! | `authors`     | list of author names                                     |
+//! | `year`        | publication year                                         |
+//! | `venue`       | name of the journal or conference                        |
+//! | `venue_kind`  | one of `journal`, `conference`, `arxiv`                  |
+//! | `id`          | optional, `arxiv:<id>`, `doi:<doi>` or any custom string |
+//! | `summary`     | optional abstract                                        |
+//! | `attachments` | list of local files, each with a `path` and a `role`     |
+//! | `ees`         | list of URLs the paper was obtained from                 |
+//! | `tags`        | optional list of tags                                    |
+//! | `collections` | optional list of collections the paper belongs to        |
+//! | `note`        | optional Markdown notes                                  |
+//! | `status`      | optional, one of `unread` (default), `reading`, `read`   |
+//! | `priority`    | optional, one of `low`, `normal` (default), `high`       |
+//! | `rating`      | optional number of stars from 1 to 5                     |
+//!
+//! The `role` of an attachment is one of `main`, `supplement`, `slides`, `arxiv` or
+//! `arxiv-v<N>` for version N of an arXiv preprint. Attachments optionally have the `size`
+//! and `sha1` digest of their file, which are used to find the file after it has been moved.
+//! Files of version 1 have a single `location` instead of `attachments`.
+//!
+//! The files backend keeps every paper in a TOML file of its own, which has the fields of a
+//! paper next to the `version`.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    library::{
+        Attachment, AttachmentRole, ContentHash, LocalPaper, Priority, Rating, ReadingStatus,
+    },
+    Identifier, PaperInfo, PaperTitle, PaperUrl, Venue,
+};
+
+use super::Format;
+
+const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LibraryFile {
+    version: u32,
+    papers: Vec<PaperEntry>,
+}
+
+/// A single paper in a TOML file of its own, as kept by the files backend.
+#[derive(Debug, Serialize, Deserialize)]
+struct PaperFile {
+    version: u32,
+    #[serde(flatten)]
+    paper: PaperEntry,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PaperEntry {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    citekey: String,
+    title: String,
+    authors: Vec<String>,
+    year: String,
+    venue: String,
+    venue_kind: VenueKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    /// Single file of papers in version 1.
+    #[serde(default, skip_serializing)]
+    location: Option<PathBuf>,
+    #[serde(default)]
+    ees: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    collections: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    note: String,
+    #[serde(default, skip_serializing_if = "is_default")]
+    status: ReadingStatus,
+    #[serde(default, skip_serializing_if = "is_default")]
+    priority: Priority,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rating: Option<u8>,
+    /// Last, since TOML requires tables after all plain values.
+    #[serde(default)]
+    attachments: Vec<AttachmentEntry>,
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AttachmentEntry {
+    path: PathBuf,
+    #[serde(with = "role")]
+    role: AttachmentRole,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha1: Option<String>,
+}
+
+mod role {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::library::AttachmentRole;
+
+    pub fn serialize<S: Serializer>(role: &AttachmentRole, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(role)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<AttachmentRole, D::Error> {
+        String::deserialize(d)?.parse().map_err(D::Error::custom)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum VenueKind {
+    Journal,
+    Conference,
+    Arxiv,
+}
+
+pub fn write(format: Format, papers: &[LocalPaper]) -> Result<String> {
+    let file = LibraryFile {
+        version: FORMAT_VERSION,
+        papers: papers.iter().map(PaperEntry::from).collect(),
+    };
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(&file)?,
+        Format::Toml => toml::to_string_pretty(&file)?,
+        Format::Zotero => bail!("Export to Zotero is not supported"),
+        Format::Bibtex | Format::Ris | Format::CslJson => {
+            bail!("{:?} is no native format", format)
+        }
+    })
+}
+
+pub fn read(format: Format, content: &str) -> Result<Vec<LocalPaper>> {
+    let file: LibraryFile = match format {
+        Format::Json => serde_json::from_str(content)?,
+        Format::Toml => toml::from_str(content)?,
+        Format::Bibtex | Format::Zotero | Format::Ris | Format::CslJson => {
+            bail!("{:?} is no native format", format)
+        }
+    };
+    if file.version > FORMAT_VERSION {
+        bail!(
+            "unsupported export version, got={}, supported={}",
+            file.version,
+            FORMAT_VERSION
+        );
+    }
+    Ok(file.papers.into_iter().map(LocalPaper::from).collect())
+}
+
+pub fn write_paper(paper: &LocalPaper) -> Result<String> {
+    let file = PaperFile {
+        version: FORMAT_VERSION,
+        paper: PaperEntry::from(paper),
+    };
+    Ok(toml::to_string_pretty(&file)?)
+}
+
+pub fn read_paper(content: &str) -> Result<LocalPaper> {
+    let file: PaperFile = toml::from_str(content)?;
+    if file.version > FORMAT_VERSION {
+        bail!(
+            "unsupported paper file version, got={}, supported={}",
+            file.version,
+            FORMAT_VERSION
+        );
+    }
+    Ok(file.paper.into())
+}
+
+impl From<&LocalPaper> for PaperEntry {
+    fn from(paper: &LocalPaper) -> Self {
+        let info = paper.metadata();
+        let (venue_kind, venue) = match &info.venue {
+            Venue::Journal(name) => (VenueKind::Journal, name),
+            Venue::Conf(name) => (VenueKind::Conference, name),
+            Venue::Arxiv(name) => (VenueKind::Arxiv, name),
+        };
+        PaperEntry {
+            citekey: paper.citekey.clone(),
+            title: info.title.to_string(),
+            authors: info.authors.clone(),
+            year: info.year.clone(),
+            venue: venue.to_owned(),
+            venue_kind,
+            id: info.id.as_ref().map(Identifier::to_prefixed_string),
+            summary: info.summary.clone(),
+            location: None,
+            attachments: paper
+                .attachments
+                .iter()
+                .map(|a| AttachmentEntry {
+                    path: a.path.clone(),
+                    role: a.role,
+                    size: a.hash.map(|h| h.size),
+                    sha1: a.hash.map(|h| h.to_string()),
+                })
+                .collect(),
+            ees: paper.ees.iter().map(|e| e.raw()).collect(),
+            tags: paper.tags.clone(),
+            collections: paper.collections.clone(),
+            note: paper.note.clone(),
+            status: paper.status,
+            priority: paper.priority,
+            rating: paper.rating.map(|r| r.stars()),
+        }
+    }
+}
+
+impl From<PaperEntry> for LocalPaper {
+    fn from(entry: PaperEntry) -> Self {
+        let venue = match entry.venue_kind {
+            VenueKind::Journal => Venue::Journal(entry.venue),
+            VenueKind::Conference => Venue::Conf(entry.venue),
+            VenueKind::Arxiv => Venue::Arxiv(entry.venue),
+        };
+        let mut paper = LocalPaper::new(
+            PaperInfo {
+                id: entry.id.as_deref().map(Identifier::parse_prefixed),
+                title: PaperTitle::new(entry.title),
+                venue,
+                authors: entry.authors,
+                year: entry.year,
+                summary: entry.summary,
+            },
+            entry
+                .location
+                .map(|location| AttachmentEntry {
+                    path: location,
+                    role: AttachmentRole::Main,
+                    size: None,
+                    sha1: None,
+                })
+                .into_iter()
+                .chain(entry.attachments)
+                .map(|a| {
+                    let mut attachment = Attachment::new(a.path, a.role);
+                    attachment.hash = match (a.size, a.sha1) {
+                        (Some(size), Some(sha1)) => ContentHash::from_hex(size, &sha1),
+                        _ => None,
+                    };
+                    attachment
+                })
+                .collect(),
+            entry.ees.into_iter().map(PaperUrl::new).collect(),
+        );
+        if !entry.citekey.is_empty() {
+            paper.citekey = entry.citekey;
+        }
+        paper.tags = entry.tags;
+        paper.collections = entry.collections;
+        paper.note = entry.note;
+        paper.status = entry.status;
+        paper.priority = entry.priority;
+        paper.rating = entry.rating.and_then(Rating::new);
+        paper
+    }
+}
@@ -0,0 +1,318 @@
+//! RIS, the tagged format of EndNote and Reference Manager, which most reference managers and
+//! publishers read and write.
+//!
+//! Every record consists of lines `XX  - value` and ends with `ER  - `. Journal papers are written
+//! as `JOUR`, conference papers as `CONF` and arXiv preprints as `UNPB`. DOIs are kept in `DO`,
+//! other identifiers prefixed as in the native format in `AN`, attachments in `L1` and notes in
+//! `N1`, one line each. RIS has no roles of files, so the first file of a paper is read as its
+//! main file and all others as supplements.
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+
+use super::{is_arxiv_venue, resolve_path, Bibliography};
+use crate::{
+    library::{Attachment, AttachmentRole, LocalPaper},
+    Doi, Identifier, PaperInfo, PaperTitle, PaperUrl, Venue,
+};
+
+pub fn write(papers: &[LocalPaper]) -> Result<String> {
+    let mut out = String::new();
+    for paper in papers {
+        write_record(&mut out, paper)?;
+    }
+    Ok(out)
+}
+
+fn write_record(out: &mut String, paper: &LocalPaper) -> Result<()> {
+    let info = paper.metadata();
+    let mut tag = |tag: &str, value: &str| -> Result<()> {
+        if !value.is_empty() {
+            // a value cannot span several lines
+            writeln!(
+                out,
+                "{}  - {}",
+                tag,
+                value.split_whitespace().collect::<Vec<_>>().join(" ")
+            )?;
+        }
+        Ok(())
+    };
+
+    let (kind, venue) = match &info.venue {
+        Venue::Journal(name) => ("JOUR", name),
+        Venue::Conf(name) => ("CONF", name),
+        Venue::Arxiv(name) => ("UNPB", name),
+    };
+    tag("TY", kind)?;
+    tag("ID", &paper.citekey)?;
+    tag("TI", &info.title.to_string())?;
+    for author in &info.authors {
+        let name = match author.rsplit_once(' ') {
+            Some((given, family)) => format!("{}, {}", family, given),
+            None => author.to_owned(),
+        };
+        tag("AU", &name)?;
+    }
+    tag("PY", &info.year)?;
+    tag("T2", venue)?;
+    match &info.id {
+        Some(Identifier::Doi(doi)) => tag("DO", &doi.to_string())?,
+        Some(id) => tag("AN", &id.to_prefixed_string())?,
+        None => {}
+    }
+    for url in &paper.ees {
+        tag("UR", &url.raw())?;
+    }
+    if let Some(summary) = &info.summary {
+        tag("AB", summary)?;
+    }
+    for keyword in &paper.tags {
+        tag("KW", keyword)?;
+    }
+    for attachment in &paper.attachments {
+        tag("L1", &attachment.path.to_string_lossy())?;
+    }
+    if !paper.note.trim().is_empty() {
+        // empty lines are kept to separate paragraphs
+        for line in paper.note.trim().lines() {
+            writeln!(out, "N1  - {}", line.trim_end())?;
+        }
+    }
+    writeln!(out, "ER  - ")?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Reads the records of the RIS file with `content`. Relative paths of linked files are resolved
+/// against `base_dir`, the absolute path of the directory of the file.
+pub fn read(content: &str, base_dir: &Path) -> Bibliography {
+    let mut bib = Bibliography::default();
+    let mut record: Vec<(String, String)> = vec![];
+    let mut records = 0;
+    for line in content.lines() {
+        let line = line.trim_start_matches('\u{feff}').trim_end();
+        match parse_line(line) {
+            Some(("ER", _)) => {
+                records += 1;
+                match to_paper(&record, base_dir, &mut bib.missing) {
+                    Ok(paper) => bib.papers.push(paper),
+                    Err(err) => {
+                        let key = match value(&record, &["ID"]) {
+                            "" => format!("record {}", records),
+                            key => key.to_owned(),
+                        };
+                        bib.unmapped.push((key, err.to_string()));
+                    }
+                }
+                record.clear();
+            }
+            Some((tag, value)) => record.push((tag.to_owned(), value.to_owned())),
+            // some writers wrap long values
+            None if !line.is_empty() => {
+                if let Some((_, value)) = record.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            }
+            None => {}
+        }
+    }
+    bib
+}
+
+/// The tag and value of a line `XX  - value`.
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let tag = line.get(..2)?;
+    let rest = line.get(2..)?.trim_start_matches(' ');
+    if !tag
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        return None;
+    }
+    let value = rest.strip_prefix('-')?;
+    Some((tag, value.trim()))
+}
+
+/// The first non-empty value of any of `tags`.
+fn value<'a>(record: &'a [(String, String)], tags: &[&str]) -> &'a str {
+    tags.iter()
+        .find_map(|tag| {
+            record
+                .iter()
+                .find(|(t, value)| t == tag && !value.is_empty())
+                .map(|(_, value)| value.as_str())
+        })
+        .unwrap_or_default()
+}
+
+fn values<'a>(record: &'a [(String, String)], tag: &'a str) -> impl Iterator<Item = &'a str> {
+    record
+        .iter()
+        .filter(move |(t, value)| t == tag && !value.is_empty())
+        .map(|(_, value)| value.as_str())
+}
+
+fn to_paper(
+    record: &[(String, String)],
+    base_dir: &Path,
+    missing: &mut Vec<PathBuf>,
+) -> Result<LocalPaper> {
+    let title = value(record, &["TI", "T1"]);
+    if title.is_empty() {
+        return Err(anyhow!("no title"));
+    }
+    let authors = values(record, "AU")
+        .chain(values(record, "A1"))
+        .map(|name| match name.split_once(", ") {
+            Some((family, given)) => format!("{} {}", given.trim(), family.trim()),
+            None => name.to_owned(),
+        })
+        .collect();
+    let year = value(record, &["PY", "Y1", "DA"])
+        .chars()
+        .filter(char::is_ascii_digit)
+        .take(4)
+        .collect();
+
+    let container = value(record, &["T2", "JO", "JF", "BT", "JA", "PB"]).to_owned();
+    let venue = match value(record, &["TY"]) {
+        "JOUR" | "JFULL" | "MGZN" | "EJOUR" if is_arxiv_venue(&container) => {
+            Venue::Arxiv(container)
+        }
+        "JOUR" | "JFULL" | "MGZN" | "EJOUR" => Venue::Journal(container),
+        "UNPB" | "PREPRINT" => Venue::Arxiv(container),
+        _ => Venue::Conf(container),
+    };
+
+    let doi = value(record, &["DO"]);
+    let id = if !doi.is_empty() {
+        Doi::parse_doi(doi.trim_start_matches("https://doi.org/"))
+            .ok()
+            .map(Identifier::Doi)
+    } else {
+        Some(value(record, &["AN"]))
+            .filter(|id| !id.is_empty())
+            .map(Identifier::parse_prefixed)
+    };
+
+    let info = PaperInfo {
+        id,
+        title: PaperTitle::new(title.to_owned()),
+        venue,
+        authors,
+        year,
+        summary: Some(value(record, &["AB", "N2"]))
+            .filter(|summary| !summary.is_empty())
+            .map(str::to_owned),
+    };
+    let mut attachments: Vec<Attachment> = vec![];
+    for path in values(record, "L1") {
+        let path = resolve_path(base_dir, path.trim_start_matches("file://"));
+        if !path.is_file() {
+            missing.push(path);
+        } else if !attachments.iter().any(|a| a.path == path) {
+            let role = if attachments.is_empty() {
+                AttachmentRole::Main
+            } else {
+                AttachmentRole::Supplement
+            };
+            attachments.push(Attachment::new(path, role));
+        }
+    }
+    let ees = values(record, "UR")
+        .map(|url| PaperUrl::new(url.to_owned()))
+        .collect();
+
+    let mut paper = LocalPaper::new(info, attachments, ees);
+    let citekey = value(record, &["ID"]);
+    if !citekey.is_empty() {
+        paper.citekey = citekey.to_owned();
+    }
+    paper.tags = values(record, "KW").map(str::to_owned).collect();
+    paper.note = record
+        .iter()
+        .filter(|(tag, _)| tag == "N1")
+        .map(|(_, line)| line.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(paper)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/formats/fixtures")
+    }
+
+    fn sample() -> Bibliography {
+        let content = std::fs::read_to_string(fixtures().join("sample.ris")).unwrap();
+        read(&content, &fixtures())
+    }
+
+    #[test]
+    fn read_sample() {
+        let bib = sample();
+        assert_eq!(bib.papers.len(), 2);
+        assert_eq!(
+            bib.unmapped,
+            [("untitled".to_owned(), "no title".to_owned())]
+        );
+        assert_eq!(
+            bib.missing,
+            [PathBuf::from("/nonexistent/im2022-slides.pdf")]
+        );
+
+        let paper = &bib.papers[0];
+        let info = paper.metadata();
+        assert_eq!(paper.citekey, "im2022nonclairvoyant");
+        assert_eq!(
+            info.authors,
+            ["Sungjin Im", "Ravi Kumar", "Mahshid Montazer Qaem"]
+        );
+        assert_eq!(info.venue, Venue::Conf("STOC".to_owned()));
+        assert_eq!(
+            info.id.as_ref().map(Identifier::to_prefixed_string),
+            Some("doi:10.1145/3519935.3520010".to_owned())
+        );
+        assert!(info.summary.as_ref().unwrap().ends_with("job sizes."));
+        assert_eq!(paper.tags, ["scheduling", "predictions"]);
+        assert_eq!(paper.attachments.len(), 1);
+        assert_eq!(paper.attachments[0].path, fixtures().join("paper.pdf"));
+        assert_eq!(paper.attachments[0].role, AttachmentRole::Main);
+        assert_eq!(
+            paper.note,
+            "Compare with the clairvoyant bounds.\n\n- Section 3 has the main algorithm."
+        );
+
+        let preprint = bib.papers[1].metadata();
+        assert_eq!(preprint.venue, Venue::Arxiv("CoRR".to_owned()));
+        assert_eq!(
+            preprint.id.as_ref().map(Identifier::to_prefixed_string),
+            Some("arxiv:2202.10199".to_owned())
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let papers = sample().papers;
+        let bib = read(&write(&papers).unwrap(), Path::new("/"));
+        assert!(bib.unmapped.is_empty() && bib.missing.is_empty());
+        assert_eq!(bib.papers.len(), papers.len());
+        for (read, written) in bib.papers.iter().zip(&papers) {
+            assert_eq!(read.metadata().title, written.metadata().title);
+            assert_eq!(read.metadata().authors, written.metadata().authors);
+            assert_eq!(read.metadata().venue, written.metadata().venue);
+            assert_eq!(read.metadata().year, written.metadata().year);
+            assert_eq!(read.metadata().summary, written.metadata().summary);
+            assert_eq!(read, written);
+        }
+    }
+}
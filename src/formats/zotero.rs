@@ -0,0 +1,497 @@
+//! Zotero libraries, read from a copy of the `zotero.sqlite` database of Zotero 5 or later and
+//! its `storage` directory.
+//!
+//! Journal articles, conference papers, preprints, books, book sections, theses and reports are
+//! imported with their creators, DOI or arXiv identifier, abstract, URL, tags, collections,
+//! child notes and PDF attachments. Nested collections are named by their path, e.g.
+//! `thesis/related work`. Items of other types are skipped.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use rusqlite::{types::ValueRef, Connection, OpenFlags, Row};
+
+use super::is_arxiv_venue;
+use crate::{
+    library::{Attachment, AttachmentRole, LocalPaper},
+    ArxivIdentifier, Doi, Identifier, PaperInfo, PaperTitle, PaperUrl, Venue,
+};
+
+/// Papers read from a Zotero database.
+#[derive(Debug, Default)]
+pub struct ZoteroLibrary {
+    pub papers: Vec<LocalPaper>,
+    /// Number of skipped items by their Zotero item type.
+    pub skipped: BTreeMap<String, usize>,
+    /// Attachments whose files do not exist.
+    pub missing: Vec<PathBuf>,
+}
+
+/// An item with its fields, e.g. `title`, `DOI` or `publicationTitle`.
+struct Item {
+    key: String,
+    kind: String,
+    fields: HashMap<String, String>,
+    /// Creators with their type, e.g. `author` or `editor`, in order.
+    creators: Vec<(String, String)>,
+    tags: Vec<String>,
+    collections: Vec<String>,
+    notes: Vec<String>,
+    files: Vec<PathBuf>,
+}
+
+impl Item {
+    fn get(&self, field: &str) -> Option<&str> {
+        self.fields
+            .get(field)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// A value of the form `Name: value` in the `extra` field, where Zotero keeps fields which
+    /// the item type has not.
+    fn extra(&self, name: &str) -> Option<&str> {
+        self.get("extra")?.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some(value.trim()).filter(|_| key.trim().eq_ignore_ascii_case(name))
+        })
+    }
+}
+
+/// Reads the items of the Zotero database at `db`, whose stored files are in `storage`. Linked
+/// files relative to the base directory set in Zotero are resolved against `base_dir`, and are
+/// missing without it.
+pub fn read(db: &Path, storage: &Path, base_dir: Option<&Path>) -> Result<ZoteroLibrary> {
+    let conn = Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("could not open Zotero database: {}", db.display()))?;
+    read_items(&conn, storage, base_dir).with_context(|| {
+        format!(
+            "could not read Zotero database {}, close Zotero or import a copy",
+            db.display()
+        )
+    })
+}
+
+fn read_items(conn: &Connection, storage: &Path, base_dir: Option<&Path>) -> Result<ZoteroLibrary> {
+    let mut items: BTreeMap<i64, Item> = BTreeMap::new();
+    query(
+        conn,
+        "SELECT items.itemID, items.key, itemTypes.typeName FROM items
+         JOIN itemTypes USING (itemTypeID)
+         WHERE items.itemID NOT IN (SELECT itemID FROM deletedItems)",
+        |row| {
+            items.insert(
+                row.get(0)?,
+                Item {
+                    key: row.get(1)?,
+                    kind: row.get(2)?,
+                    fields: HashMap::new(),
+                    creators: vec![],
+                    tags: vec![],
+                    collections: vec![],
+                    notes: vec![],
+                    files: vec![],
+                },
+            );
+            Ok(())
+        },
+    )?;
+    query(
+        conn,
+        "SELECT itemData.itemID, fieldsCombined.fieldName, itemDataValues.value FROM itemData
+         JOIN fieldsCombined USING (fieldID)
+         JOIN itemDataValues USING (valueID)",
+        |row| {
+            if let Some(item) = items.get_mut(&row.get(0)?) {
+                item.fields.insert(row.get(1)?, text(row, 2)?);
+            }
+            Ok(())
+        },
+    )?;
+    query(
+        conn,
+        "SELECT itemCreators.itemID, creators.firstName, creators.lastName,
+                creatorTypes.creatorType FROM itemCreators
+         JOIN creators USING (creatorID)
+         JOIN creatorTypes USING (creatorTypeID)
+         ORDER BY itemCreators.itemID, itemCreators.orderIndex",
+        |row| {
+            if let Some(item) = items.get_mut(&row.get(0)?) {
+                let name = format!("{} {}", text(row, 1)?, text(row, 2)?);
+                item.creators.push((name.trim().to_owned(), row.get(3)?));
+            }
+            Ok(())
+        },
+    )?;
+    query(
+        conn,
+        "SELECT itemTags.itemID, tags.name FROM itemTags JOIN tags USING (tagID)",
+        |row| {
+            if let Some(item) = items.get_mut(&row.get(0)?) {
+                item.tags.push(row.get(1)?);
+            }
+            Ok(())
+        },
+    )?;
+
+    let mut collections: HashMap<i64, (String, Option<i64>)> = HashMap::new();
+    query(
+        conn,
+        "SELECT collectionID, collectionName, parentCollectionID FROM collections",
+        |row| {
+            collections.insert(row.get(0)?, (row.get(1)?, row.get(2)?));
+            Ok(())
+        },
+    )?;
+    query(
+        conn,
+        "SELECT itemID, collectionID FROM collectionItems",
+        |row| {
+            if let Some(item) = items.get_mut(&row.get(0)?) {
+                item.collections
+                    .push(collection_path(&collections, row.get(1)?));
+            }
+            Ok(())
+        },
+    )?;
+
+    // child items are merged into their parents, so they are not imported on their own
+    let mut children = vec![];
+    let mut missing = vec![];
+    query(
+        conn,
+        "SELECT itemID, parentItemID, note FROM itemNotes WHERE parentItemID IS NOT NULL",
+        |row| {
+            children.push(row.get::<_, i64>(0)?);
+            if let Some(parent) = items.get_mut(&row.get(1)?) {
+                parent.notes.push(html_to_text(&text(row, 2)?));
+            }
+            Ok(())
+        },
+    )?;
+    let mut attachments = vec![];
+    query(
+        conn,
+        "SELECT itemID, parentItemID, linkMode, contentType, path FROM itemAttachments
+         WHERE parentItemID IS NOT NULL",
+        |row| {
+            let path: Option<String> = row.get(4)?;
+            attachments.push((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                path.unwrap_or_default(),
+            ));
+            Ok(())
+        },
+    )?;
+    for (id, parent, link_mode, content_type, path) in attachments {
+        children.push(id);
+        let key = match (items.get(&id), items.contains_key(&parent)) {
+            (Some(attachment), true) => attachment.key.clone(),
+            _ => continue,
+        };
+        let path = match (link_mode, path.split_once(':')) {
+            // files imported into Zotero's storage
+            (0 | 1, Some(("storage", name))) => storage.join(key).join(name),
+            // linked files relative to the base directory
+            (2, Some(("attachments", relative))) => match base_dir {
+                Some(base_dir) => base_dir.join(relative),
+                None => PathBuf::from(&path),
+            },
+            // other linked files
+            (2, _) => PathBuf::from(&path),
+            _ => continue,
+        };
+        let is_pdf = content_type == "application/pdf"
+            || path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        if !is_pdf {
+            continue;
+        }
+        if path.is_absolute() && path.is_file() {
+            items.get_mut(&parent).unwrap().files.push(path);
+        } else {
+            missing.push(path);
+        }
+    }
+    for child in children {
+        items.remove(&child);
+    }
+
+    let mut library = ZoteroLibrary {
+        missing,
+        ..Default::default()
+    };
+    for item in items.into_values() {
+        match to_paper(&item) {
+            Some(paper) => library.papers.push(paper),
+            // annotations always belong to attachments
+            None if item.kind == "annotation" => {}
+            None => *library.skipped.entry(item.kind).or_default() += 1,
+        }
+    }
+    Ok(library)
+}
+
+/// Runs the SQL `sql` and calls `f` with every resulting row.
+fn query(
+    conn: &Connection,
+    sql: &str,
+    mut f: impl FnMut(&Row) -> rusqlite::Result<()>,
+) -> Result<()> {
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        f(row)?;
+    }
+    Ok(())
+}
+
+/// The value of a column as text, as Zotero also stores numbers in text fields.
+fn text(row: &Row, column: usize) -> rusqlite::Result<String> {
+    Ok(match row.get_ref(column)? {
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+        ValueRef::Integer(n) => n.to_string(),
+        ValueRef::Real(x) => x.to_string(),
+        ValueRef::Null | ValueRef::Blob(_) => String::new(),
+    })
+}
+
+fn collection_path(collections: &HashMap<i64, (String, Option<i64>)>, id: i64) -> String {
+    let mut path = vec![];
+    let mut next = Some(id);
+    // the length limit guards against cycles
+    while let Some((name, parent)) = next.and_then(|id| collections.get(&id)) {
+        if path.len() > collections.len() {
+            break;
+        }
+        path.push(name.as_str());
+        next = *parent;
+    }
+    path.reverse();
+    path.join("/")
+}
+
+fn to_paper(item: &Item) -> Option<LocalPaper> {
+    let venue = match item.kind.as_str() {
+        "journalArticle" => {
+            let journal = item.get("publicationTitle").unwrap_or_default().to_owned();
+            if is_arxiv_venue(&journal) {
+                Venue::Arxiv(journal)
+            } else {
+                Venue::Journal(journal)
+            }
+        }
+        "conferencePaper" => Venue::Conf(
+            item.get("proceedingsTitle")
+                .or_else(|| item.get("conferenceName"))
+                .unwrap_or_default()
+                .to_owned(),
+        ),
+        "preprint" => Venue::Arxiv(item.get("repository").unwrap_or("arXiv").to_owned()),
+        "bookSection" => Venue::Conf(item.get("bookTitle").unwrap_or_default().to_owned()),
+        "book" | "thesis" | "report" => Venue::Conf(
+            ["publisher", "university", "institution"]
+                .iter()
+                .find_map(|field| item.get(field))
+                .unwrap_or_default()
+                .to_owned(),
+        ),
+        _ => return None,
+    };
+    let title = item.get("title")?;
+
+    let doi = item
+        .get("DOI")
+        .or_else(|| item.extra("DOI"))
+        .and_then(|doi| Doi::parse_doi(doi).ok());
+    // arXiv registers DOIs like 10.48550/arXiv.2101.01234 for its preprints
+    let arxiv_doi = doi
+        .as_ref()
+        .map(|doi| doi.to_string().to_lowercase())
+        .and_then(|doi| doi.strip_prefix("10.48550/arxiv.").map(str::to_owned));
+    let arxiv = item
+        .get("archiveID")
+        .or_else(|| item.extra("arXiv"))
+        .map(|id| id.trim_start_matches("arXiv:").to_owned())
+        .or(arxiv_doi)
+        .and_then(|id| ArxivIdentifier::parse_string(&id).ok());
+    let id = match (&doi, arxiv) {
+        (_, Some(arxiv)) if matches!(venue, Venue::Arxiv(_)) => Some(Identifier::Arxiv(arxiv)),
+        (Some(doi), _) => Some(Identifier::Doi(doi.clone())),
+        (None, arxiv) => arxiv.map(Identifier::Arxiv),
+    };
+
+    let mut authors: Vec<String> = item
+        .creators
+        .iter()
+        .filter(|(_, kind)| kind == "author")
+        .map(|(name, _)| name.clone())
+        .collect();
+    if authors.is_empty() {
+        authors = item.creators.iter().map(|(name, _)| name.clone()).collect();
+    }
+
+    let mut ees = vec![];
+    if let Some(url) = item.get("url") {
+        ees.push(PaperUrl::new(url.to_owned()));
+    }
+    if let Some(doi) = &doi {
+        ees.push(PaperUrl::new(format!("https://doi.org/{}", doi)));
+    }
+    let attachments = item
+        .files
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let role = if i == 0 {
+                AttachmentRole::Main
+            } else {
+                AttachmentRole::Supplement
+            };
+            Attachment::new(path, role)
+        })
+        .collect();
+
+    let metadata = PaperInfo {
+        id,
+        title: PaperTitle::new(title.to_owned()),
+        venue,
+        authors,
+        year: item
+            .get("date")
+            .and_then(|date| date.get(..4))
+            .filter(|year| year.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or_default()
+            .to_owned(),
+        summary: item.get("abstractNote").map(str::to_owned),
+    };
+    let mut paper = LocalPaper::new(metadata, attachments, ees);
+    if let Some(key) = item
+        .get("citationKey")
+        .or_else(|| item.extra("Citation Key"))
+    {
+        paper.citekey = key.to_owned();
+    }
+    paper.tags = item.tags.clone();
+    paper.collections = item.collections.clone();
+    paper.note = item.notes.join("\n\n");
+    Some(paper)
+}
+
+/// Plain text of a Zotero note, which is HTML.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('>')
+            .map_or(rest.len(), |end| start + end + 1);
+        let tag = rest[start..end].to_lowercase();
+        if ["</p", "<br", "</h", "</li", "</div"]
+            .iter()
+            .any(|t| tag.starts_with(t))
+        {
+            text.push('\n');
+        }
+        rest = &rest[end..];
+    }
+    text.push_str(rest);
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .trim()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/formats/fixtures/zotero")
+    }
+
+    fn sample(base_dir: Option<&Path>) -> ZoteroLibrary {
+        let dir = fixtures();
+        read(&dir.join("zotero.sqlite"), &dir.join("storage"), base_dir).unwrap()
+    }
+
+    #[test]
+    fn read_sample() {
+        let dir = fixtures();
+        let zotero = sample(Some(&dir.join("linked")));
+        assert_eq!(zotero.papers.len(), 2);
+        assert_eq!(
+            zotero.skipped.into_iter().collect::<Vec<_>>(),
+            [("note".to_owned(), 1), ("webpage".to_owned(), 1)]
+        );
+        assert_eq!(zotero.missing, [PathBuf::from("/nonexistent/slides.pdf")]);
+
+        let paper = &zotero.papers[0];
+        let info = paper.metadata();
+        assert_eq!(paper.citekey, "im2021scheduling");
+        assert_eq!(
+            info.title.to_string(),
+            "Non-Clairvoyant Scheduling with Predictions"
+        );
+        assert_eq!(info.venue, Venue::Conf("SPAA".to_owned()));
+        assert_eq!(info.authors, ["Sungjin Im", "Ravi Kumar"]);
+        assert_eq!(info.year, "2021");
+        assert!(matches!(info.id, Some(Identifier::Doi(_))));
+        assert_eq!(paper.tags, ["scheduling", "predictions"]);
+        assert_eq!(paper.collections, ["thesis/related work"]);
+        assert_eq!(paper.note, "Read section 3\na & b");
+        assert_eq!(
+            paper.attachments,
+            [Attachment::new(
+                dir.join("storage/STOR1111/paper.pdf"),
+                AttachmentRole::Main
+            )]
+        );
+
+        let paper = &zotero.papers[1];
+        let info = paper.metadata();
+        assert!(matches!(info.venue, Venue::Arxiv(_)));
+        assert!(matches!(info.id, Some(Identifier::Arxiv(_))));
+        assert_eq!(paper.collections, ["thesis"]);
+        assert_eq!(
+            paper.attachments,
+            [Attachment::new(
+                dir.join("linked/preprints/learning.pdf"),
+                AttachmentRole::Main
+            )]
+        );
+    }
+
+    #[test]
+    fn relative_links_need_base_dir() {
+        let zotero = sample(None);
+        assert!(zotero.papers[1].attachments.is_empty());
+        assert_eq!(
+            zotero.missing,
+            [
+                PathBuf::from("attachments:preprints/learning.pdf"),
+                PathBuf::from("/nonexistent/slides.pdf")
+            ]
+        );
+    }
+
+    #[test]
+    fn notes_as_text() {
+        assert_eq!(
+            html_to_text("<h1>Title</h1><p>x &lt; y<br/>and&nbsp;&quot;z&quot;</p>"),
+            "Title\nx < y\nand \"z\""
+        );
+    }
+}
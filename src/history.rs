@@ -0,0 +1,51 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use clap::Parser;
+use console::style;
+
+use crate::{
+    library::journal::{self, OperationKind},
+    xiv_config::Config,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "List the recent changes to your library")]
+pub struct History {
+    /// Number of operations to show
+    #[clap(short, default_value = "20")]
+    n: usize,
+}
+
+impl History {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let ops = journal::read(&config.data_dir)?;
+        if ops.is_empty() {
+            println!("No changes yet.");
+            return Ok(());
+        }
+
+        let reverted: HashSet<u64> = ops
+            .iter()
+            .filter_map(|op| match op.kind {
+                OperationKind::Revert(id) => Some(id),
+                _ => None,
+            })
+            .collect();
+        for op in ops.iter().rev().take(self.n) {
+            let line = format!(
+                "{:>4}  {}  {:<10} {}",
+                op.id,
+                op.time,
+                op.kind.to_string(),
+                op.summary()
+            );
+            if reverted.contains(&op.id) {
+                println!("{} {}", style(line).dim(), style("(undone)").dim());
+            } else {
+                println!("{}", line);
+            }
+        }
+        Ok(())
+    }
+}
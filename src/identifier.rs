@@ -9,6 +9,32 @@ pub enum Identifier {
     Custom(String),
 }
 
+impl Identifier {
+    /// Formats the identifier as `arxiv:<id>`, `doi:<doi>` or the plain custom string.
+    pub fn to_prefixed_string(&self) -> String {
+        match self {
+            Identifier::Arxiv(arxiv) => format!("arxiv:{}", arxiv),
+            Identifier::Doi(doi) => format!("doi:{}", doi),
+            Identifier::Custom(custom) => custom.to_owned(),
+        }
+    }
+
+    /// Inverse of `to_prefixed_string`.
+    pub fn parse_prefixed(id: &str) -> Identifier {
+        if let Some(arxiv) = id.strip_prefix("arxiv:") {
+            if let Ok(arxiv) = ArxivIdentifier::parse_string(arxiv) {
+                return Identifier::Arxiv(arxiv);
+            }
+        }
+        if let Some(doi) = id.strip_prefix("doi:") {
+            if let Ok(doi) = Doi::parse_doi(doi) {
+                return Identifier::Doi(doi);
+            }
+        }
+        Identifier::Custom(id.to_owned())
+    }
+}
+
 impl std::fmt::Display for Identifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -28,7 +54,7 @@ pub struct ArxivIdentifier {
 
 impl ArxivIdentifier {
     pub fn parse_string(id: &str) -> Result<Self> {
-        let temp = id.split('/').last().unwrap();
+        let temp = id.split('/').next_back().unwrap();
         let re = Regex::new(r"(\d{2})(\d{2})\.?(\d+)").unwrap();
         if let Some(capture) = re.captures(temp) {
             Ok(ArxivIdentifier {
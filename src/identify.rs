@@ -0,0 +1,240 @@
+//! Identifiers and titles of papers found in PDF files, to look the papers up online.
+
+use std::{path::Path, sync::LazyLock};
+
+use regex::Regex;
+
+use crate::{
+    pdf::{PdfInfo, TextLine},
+    ArxivIdentifier, Doi, Identifier,
+};
+
+/// Maximal number of lines of a title.
+const TITLE_LINES: usize = 4;
+
+static STAMP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"arXiv:\s*(\d{4}\.\d{4,5})(?:v\d+)?\s*\[[-\w.]+\]").unwrap());
+static DOI_LABEL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:\bdoi:?\s*|doi\.org/)(10\.\d{4,9}/[-._;()/:A-Za-z0-9]+)").unwrap()
+});
+static MENTION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:arxiv:\s*|arxiv\.org/(?:abs|pdf)/)(\d{4}\.\d{4,5})").unwrap()
+});
+static DOI_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(10\.\d{4,9}/[-._;()/:A-Za-z0-9]+)").unwrap());
+static STAMP_START_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^arXiv:\s*\d{4}\.\d{4,5}").unwrap());
+static FILENAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^\d.])(\d{4}\.\d{4,5})(?:v\d+)?(?:$|[^\d])").unwrap());
+
+/// Where an identifier was found, from the most to the least reliable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    /// The stamp arXiv puts into the margin of the first page, e.g.
+    /// `arXiv:2101.01234v2 [cs.DS] 4 Jan 2021`.
+    Stamp,
+    /// The name of the file, e.g. `2101.01234v2.pdf`.
+    FileName,
+    /// A DOI given as such, e.g. `DOI: 10.1145/3519935.3520006` or `https://doi.org/...`.
+    Doi,
+    /// An arXiv identifier mentioned in the text, which might be a reference to another paper.
+    Mention,
+    /// Any other DOI in the text.
+    Text,
+}
+
+impl Source {
+    /// Whether the identifier most likely belongs to the paper itself.
+    pub fn is_reliable(self) -> bool {
+        self <= Source::Doi
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub id: Identifier,
+    pub source: Source,
+}
+
+/// Identifiers of the paper in the PDF at `path` with `info`, most likely first.
+pub fn identifiers(path: &Path, info: &PdfInfo) -> Vec<Found> {
+    let mut found = vec![];
+    if let Some(id) = in_filename(path) {
+        found.push(Found {
+            id,
+            source: Source::FileName,
+        });
+    }
+    for line in &info.text {
+        let captures = |re: &Regex| -> Vec<String> {
+            re.captures_iter(&line.text)
+                .map(|capture| capture[1].to_owned())
+                .collect()
+        };
+        for (re, source) in [(&*STAMP_RE, Source::Stamp), (&*MENTION_RE, Source::Mention)] {
+            for id in captures(re).iter().filter_map(|id| arxiv(id)) {
+                found.push(Found {
+                    id: Identifier::Arxiv(id),
+                    source,
+                });
+            }
+        }
+        for (re, source) in [(&*DOI_LABEL_RE, Source::Doi), (&*DOI_RE, Source::Text)] {
+            for doi in captures(re) {
+                if let Ok(doi) = Doi::parse_doi(doi.trim_end_matches(['.', ',', ';', ')'])) {
+                    found.push(Found {
+                        id: Identifier::Doi(doi),
+                        source,
+                    });
+                }
+            }
+        }
+    }
+
+    // the stable sort keeps the order of appearance among equally reliable identifiers
+    found.sort_by_key(|f| f.source);
+    let mut unique: Vec<Found> = vec![];
+    for f in found {
+        if !unique.iter().any(|u| u.id == f.id) {
+            unique.push(f);
+        }
+    }
+    unique
+}
+
+/// The title in the metadata of the PDF, or else the title block on its first page.
+pub fn title(info: &PdfInfo) -> Option<String> {
+    info.title.clone().or_else(|| title_block(&info.text))
+}
+
+/// The first lines on the first page which are set in the largest font, as the title of most
+/// papers is. None if no text on the first page is larger than the body text.
+pub fn title_block(lines: &[TextLine]) -> Option<String> {
+    let lines: Vec<&TextLine> = lines
+        .iter()
+        .filter(|line| line.page == 1 && line.size > 0.0 && !STAMP_START_RE.is_match(&line.text))
+        .filter(|line| line.text.chars().filter(|c| c.is_alphabetic()).count() >= 2)
+        .collect();
+    let largest = lines.iter().map(|line| line.size).fold(0.0, f32::max);
+    if largest <= body_size(&lines)? + 0.5 {
+        return None;
+    }
+
+    let mut title = String::new();
+    for line in lines
+        .iter()
+        .skip_while(|line| line.size < largest - 0.5)
+        .take_while(|line| line.size >= largest - 0.5)
+        .take(TITLE_LINES)
+    {
+        // titles are rarely hyphenated, so a hyphen at the end of a line is part of a word
+        if !title.is_empty() && !title.ends_with('-') {
+            title.push(' ');
+        }
+        title.push_str(&line.text);
+    }
+    Some(title).filter(|title| title.split_whitespace().count() >= 2)
+}
+
+/// The font size of most of the text.
+fn body_size(lines: &[&TextLine]) -> Option<f32> {
+    let mut sizes: Vec<(f32, usize)> = vec![];
+    for line in lines {
+        match sizes
+            .iter_mut()
+            .find(|(size, _)| (size - line.size).abs() < 0.5)
+        {
+            Some((_, chars)) => *chars += line.text.len(),
+            None => sizes.push((line.size, line.text.len())),
+        }
+    }
+    sizes
+        .into_iter()
+        .max_by_key(|(_, chars)| *chars)
+        .map(|(size, _)| size)
+}
+
+/// An arXiv identifier of the new style `YYMM.NNNNN` in the name of the file, e.g.
+/// `2202.01234v2.pdf`.
+fn in_filename(path: &Path) -> Option<Identifier> {
+    let name = path.file_stem()?.to_str()?;
+    let id = FILENAME_RE
+        .captures_iter(name)
+        .find_map(|capture| arxiv(&capture[1]))?;
+    Some(Identifier::Arxiv(id))
+}
+
+/// Parses `YYMM.NNNNN` if the month is valid, which rules out most other numbers.
+fn arxiv(id: &str) -> Option<ArxivIdentifier> {
+    let month: u32 = id.get(2..4)?.parse().ok()?;
+    if (1..=12).contains(&month) {
+        ArxivIdentifier::parse_string(id).ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(lines: &[&str]) -> PdfInfo {
+        PdfInfo {
+            text: lines
+                .iter()
+                .map(|text| TextLine {
+                    text: text.to_string(),
+                    size: 10.0,
+                    page: 1,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn ids(path: &str, lines: &[&str]) -> Vec<(String, Source)> {
+        identifiers(Path::new(path), &info(lines))
+            .into_iter()
+            .map(|found| (found.id.to_prefixed_string(), found.source))
+            .collect()
+    }
+
+    #[test]
+    fn arxiv_identifiers() {
+        assert_eq!(
+            ids(
+                "downloads/2011.09076v2.pdf",
+                &[
+                    "arXiv:2101.01234v3 [cs.DS] 4 Jan 2021",
+                    "see arXiv: 1905.12345 and https://arxiv.org/abs/2011.09076",
+                ]
+            ),
+            [
+                ("arxiv:2101.01234".to_owned(), Source::Stamp),
+                ("arxiv:2011.09076".to_owned(), Source::FileName),
+                ("arxiv:1905.12345".to_owned(), Source::Mention),
+            ]
+        );
+        // numbers without a valid month or with more digits are no identifiers
+        assert!(ids("scan_2099.12345.pdf", &[]).is_empty());
+        assert!(ids("report-12011.09076.pdf", &["version 1.2345"]).is_empty());
+    }
+
+    #[test]
+    fn dois() {
+        assert_eq!(
+            ids(
+                "paper.pdf",
+                &[
+                    "cited as (10.1007/978-3-030-12345-6_7).",
+                    "DOI: 10.1145/3519935.3520006, and https://doi.org/10.4230/LIPIcs.ICALP.2022.12",
+                ]
+            ),
+            [
+                ("doi:10.1145/3519935.3520006".to_owned(), Source::Doi),
+                ("doi:10.4230/LIPIcs.ICALP.2022.12".to_owned(), Source::Doi),
+                ("doi:10.1007/978-3-030-12345-6_7".to_owned(), Source::Text),
+            ]
+        );
+    }
+}
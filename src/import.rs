@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::{
+    formats::{bibtex, csl, native, ris, Bibliography, Format},
+    import_dir::DirImport,
+    library::{Library, LocalPaper},
+    xiv_config::Config,
+};
+
+#[derive(Parser, Debug)]
+#[clap(
+    about = "Import papers from a file written by xivar export, a BibTeX, RIS or CSL-JSON file, or a directory of PDFs"
+)]
+pub struct Import {
+    /// File written by xivar export, BibTeX, RIS or CSL-JSON file, or directory whose PDFs are looked up online
+    input: PathBuf,
+
+    /// Input format, guessed from the file extension if omitted
+    #[clap(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// Only print the review report of a directory import without changing anything
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Add the matched papers of a directory import without asking
+    #[clap(short, long)]
+    yes: bool,
+
+    /// Also write the review report of a directory import as JSON to this file
+    #[clap(long)]
+    report: Option<PathBuf>,
+
+    /// Storage directory of a Zotero database, defaults to `storage` next to it
+    #[clap(long)]
+    storage: Option<PathBuf>,
+
+    /// Base directory of the linked files of a Zotero database, as set in Zotero's preferences
+    #[clap(long)]
+    base_dir: Option<PathBuf>,
+}
+
+impl Import {
+    pub async fn run(&self, config: Config) -> Result<()> {
+        if self.input.is_dir() {
+            return DirImport {
+                dir: &self.input,
+                yes: self.yes,
+                dry_run: self.dry_run,
+                report: self.report.as_deref(),
+            }
+            .run(&config)
+            .await;
+        }
+
+        let format = match self.format {
+            Some(format) => format,
+            None => Format::from_path(&self.input)?,
+        };
+        let read = || {
+            std::fs::read_to_string(&self.input)
+                .with_context(|| format!("could not read {}", self.input.display()))
+        };
+        let base_dir = || -> Result<PathBuf> {
+            let input = self.input.canonicalize()?;
+            Ok(input.parent().unwrap_or_else(|| Path::new("/")).to_owned())
+        };
+        let papers = match format {
+            Format::Bibtex => report(bibtex::read(&read()?, &base_dir()?)),
+            Format::Ris => report(ris::read(&read()?, &base_dir()?)),
+            Format::CslJson => report(csl::read(&read()?, &base_dir()?)?),
+            #[cfg(feature = "sqlite")]
+            Format::Zotero => {
+                let input = self.input.canonicalize()?;
+                let storage = match &self.storage {
+                    Some(storage) => storage.canonicalize()?,
+                    None => input.with_file_name("storage"),
+                };
+                let base_dir = match &self.base_dir {
+                    Some(base_dir) => Some(base_dir.canonicalize()?),
+                    None => None,
+                };
+                let zotero = crate::formats::zotero::read(&input, &storage, base_dir.as_deref())?;
+                for path in &zotero.missing {
+                    println!("Skipping missing file {}", path.display());
+                }
+                let relative = |path: &PathBuf| path.to_string_lossy().starts_with("attachments:");
+                if base_dir.is_none() && zotero.missing.iter().any(relative) {
+                    println!(
+                        "Give the base directory of linked files with --base-dir to import them."
+                    );
+                }
+                for (kind, count) in &zotero.skipped {
+                    println!("Skipping items of type {} ({})", kind, count);
+                }
+                zotero.papers
+            }
+            #[cfg(not(feature = "sqlite"))]
+            Format::Zotero => {
+                anyhow::bail!("xivar was built without SQLite support, enable the `sqlite` feature")
+            }
+            format => native::read(format, &read()?)?,
+        };
+
+        let mut lib = Library::open(&config)?;
+        let before = lib.size();
+        let total = papers.len();
+        for paper in papers {
+            lib.add(paper);
+        }
+        let added = lib.size() - before;
+        println!(
+            "Imported {} papers ({} new, {} merged into existing entries).",
+            total,
+            added,
+            total - added
+        );
+        lib.save()
+    }
+}
+
+/// Prints the missing files and the skipped entries of `bib`, and returns its papers.
+fn report(bib: Bibliography) -> Vec<LocalPaper> {
+    for path in &bib.missing {
+        println!("Skipping missing file {}", path.display());
+    }
+    for (key, reason) in &bib.unmapped {
+        println!("Skipping {}: {}", key, reason);
+    }
+    bib.papers
+}
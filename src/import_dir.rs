@@ -0,0 +1,351 @@
+//! Bulk import of a directory tree of PDFs which are not in the library yet.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use console::style;
+use serde::Serialize;
+
+use crate::{
+    identify,
+    library::{
+        inbox::{self, InboxEntry},
+        ContentHash, Library, LocalPaper,
+    },
+    pdf::{self, PdfInfo},
+    resolve::{self, Candidate, RateLimiter},
+    util::{confirm, visit_files},
+    xiv_config::Config,
+    ArxivIdentifier, Identifier, PaperHit,
+};
+
+/// Number of preprints looked up with a single request to arXiv.
+const ARXIV_BATCH: usize = 50;
+
+/// arXiv asks for at most one request every three seconds.
+const ARXIV_INTERVAL: Duration = Duration::from_secs(3);
+const DOI_INTERVAL: Duration = Duration::from_secs(1);
+/// Title searches query both DBLP and arXiv.
+const SEARCH_INTERVAL: Duration = Duration::from_secs(3);
+
+pub struct DirImport<'a> {
+    pub dir: &'a Path,
+    pub yes: bool,
+    pub dry_run: bool,
+    pub report: Option<&'a Path>,
+}
+
+/// A PDF found in the directory.
+struct Scanned {
+    path: PathBuf,
+    info: PdfInfo,
+    title: Option<String>,
+    id: Option<Identifier>,
+}
+
+#[derive(Serialize)]
+struct Review {
+    path: PathBuf,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "outcome", rename_all = "lowercase")]
+enum Outcome {
+    /// Found online with confidence, by the given means.
+    Matched {
+        paper: Box<LocalPaper>,
+        by: String,
+    },
+    /// Found online, but without confidence which of the candidates it is.
+    Ambiguous {
+        candidates: Vec<Candidate>,
+    },
+    Unmatched {
+        reason: String,
+    },
+}
+
+impl DirImport<'_> {
+    pub async fn run(&self, config: &Config) -> Result<()> {
+        let mut lib = Library::open(config)?;
+        let scanned = scan(&lib, self.dir)?;
+        if scanned.is_empty() {
+            println!("No new PDFs in {}.", self.dir.display());
+            return Ok(());
+        }
+
+        let reviews = lookup(scanned).await;
+        print_report(&reviews);
+        if let Some(report) = self.report {
+            std::fs::write(report, serde_json::to_string_pretty(&reviews)?)
+                .with_context(|| format!("could not write report to {}", report.display()))?;
+            println!("Wrote report to {}", report.display());
+        }
+
+        let matched = reviews
+            .iter()
+            .filter(|r| matches!(r.outcome, Outcome::Matched { .. }))
+            .count();
+        let queued = reviews.len() - matched;
+        if self.dry_run
+            || !(self.yes
+                || confirm(&format!(
+                    "Add {} matched papers to your library and queue {} files in your inbox?",
+                    matched, queued
+                ))?)
+        {
+            return Ok(());
+        }
+
+        let mut entries = vec![];
+        for review in reviews {
+            match review.outcome {
+                Outcome::Matched { paper, .. } => lib.add(*paper),
+                Outcome::Ambiguous { candidates } => entries.push(InboxEntry::new(
+                    review.path,
+                    "no confident match online",
+                    candidates,
+                )),
+                Outcome::Unmatched { reason } => {
+                    entries.push(InboxEntry::new(review.path, &reason, vec![]))
+                }
+            }
+        }
+        lib.save()?;
+        inbox::update(&config.data_dir, |inbox| {
+            for entry in entries {
+                if !inbox.iter().any(|e| e.path == entry.path) {
+                    inbox.push(entry);
+                }
+            }
+        })?;
+        println!(
+            "Added {} papers, queued {} files in your inbox, see `xivar inbox`.",
+            matched, queued
+        );
+        Ok(())
+    }
+}
+
+/// Reads all PDFs below `dir` whose files are not in the library yet.
+fn scan(lib: &Library, dir: &Path) -> Result<Vec<Scanned>> {
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("cannot open {}", dir.display()))?;
+    let mut known: HashSet<ContentHash> = lib
+        .iter()
+        .flat_map(|p| &p.attachments)
+        .filter_map(|a| a.hash)
+        .collect();
+    let mut paths = vec![];
+    visit_files(&dir, &mut |path, _| {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+        {
+            paths.push(path.to_path_buf());
+        }
+    });
+    paths.sort();
+
+    let mut scanned = vec![];
+    for (i, path) in paths.iter().enumerate() {
+        progress("Reading PDFs", i + 1, paths.len());
+        if lib.find_paper_by_path(path).is_some() {
+            continue;
+        }
+        match ContentHash::of_file(path) {
+            // also skips copies within the directory
+            Ok(hash) if !known.insert(hash) => continue,
+            Ok(_) => {}
+            Err(err) => log::warn!("{:#}", err),
+        }
+        let info = pdf::read_info(path).unwrap_or_else(|err| {
+            log::warn!("{:#}", err);
+            PdfInfo::default()
+        });
+        // identifiers mentioned in the text may belong to cited papers, so such files are
+        // searched by their title instead
+        let id = identify::identifiers(path, &info)
+            .into_iter()
+            .find(|found| found.source.is_reliable())
+            .map(|found| found.id);
+        scanned.push(Scanned {
+            path: path.clone(),
+            title: identify::title(&info),
+            info,
+            id,
+        });
+    }
+    println!(
+        "Found {} new PDFs, skipped {} already in your library or found twice.",
+        scanned.len(),
+        paths.len() - scanned.len()
+    );
+    Ok(scanned)
+}
+
+/// Looks up the identified files first, in batches where the remote allows, and searches the
+/// titles of the remaining ones.
+async fn lookup(scanned: Vec<Scanned>) -> Vec<Review> {
+    let arxiv_ids: Vec<ArxivIdentifier> = scanned
+        .iter()
+        .filter_map(|s| match &s.id {
+            Some(Identifier::Arxiv(id)) => Some(id.clone()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let mut preprints: HashMap<ArxivIdentifier, PaperHit> = HashMap::new();
+    let mut limiter = RateLimiter::new(ARXIV_INTERVAL);
+    for (i, batch) in arxiv_ids.chunks(ARXIV_BATCH).enumerate() {
+        progress(
+            "Looking up arXiv ids",
+            i * ARXIV_BATCH + batch.len(),
+            arxiv_ids.len(),
+        );
+        limiter.wait().await;
+        match resolve::lookup_arxiv(batch).await {
+            Ok(hits) => {
+                preprints.extend(hits.into_iter().filter_map(
+                    |hit| match hit.metadata().id.clone() {
+                        Some(Identifier::Arxiv(id)) => Some((id, hit)),
+                        _ => None,
+                    },
+                ))
+            }
+            Err(err) => log::warn!("Could not look up arXiv ids: {}", err),
+        }
+    }
+
+    let mut doi_limiter = RateLimiter::new(DOI_INTERVAL);
+    let mut search_limiter = RateLimiter::new(SEARCH_INTERVAL);
+    let total = scanned.len();
+    let mut reviews = vec![];
+    for (
+        i,
+        Scanned {
+            path,
+            info,
+            title,
+            id,
+        },
+    ) in scanned.into_iter().enumerate()
+    {
+        progress("Looking up files", i + 1, total);
+        let found = match &id {
+            Some(Identifier::Arxiv(arxiv)) => preprints
+                .get(arxiv)
+                .cloned()
+                .and_then(|hit| resolve::to_local(hit, &path))
+                .map(|paper| (paper, "arXiv id")),
+            Some(Identifier::Doi(doi)) => {
+                doi_limiter.wait().await;
+                match resolve::lookup_doi(doi).await {
+                    Ok(info) => info.map(|info| (resolve::doi_to_local(info, doi, &path), "DOI")),
+                    Err(err) => {
+                        log::warn!("Could not look up {}: {}", doi, err);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let outcome = match (found, &title) {
+            (Some((paper, by)), _) => Outcome::Matched {
+                paper: Box::new(paper),
+                by: by.to_owned(),
+            },
+            (None, Some(title)) => {
+                search_limiter.wait().await;
+                search(title, &info.authors, &path).await
+            }
+            (None, None) => Outcome::Unmatched {
+                reason: match id {
+                    Some(id) => format!("{} not found online", id.to_prefixed_string()),
+                    None => "no identifier or title found".to_owned(),
+                },
+            },
+        };
+        reviews.push(Review { path, outcome });
+    }
+    reviews
+}
+
+async fn search(title: &str, authors: &[String], path: &Path) -> Outcome {
+    match resolve::search(title, authors, path).await {
+        Ok(mut candidates) => match candidates.first() {
+            Some(best) if best.score >= resolve::CONFIDENT => Outcome::Matched {
+                paper: Box::new(candidates.swap_remove(0).paper),
+                by: "title".to_owned(),
+            },
+            Some(_) => {
+                candidates.truncate(5);
+                Outcome::Ambiguous { candidates }
+            }
+            None => Outcome::Unmatched {
+                reason: "not found online".to_owned(),
+            },
+        },
+        Err(err) => Outcome::Unmatched {
+            reason: err.to_string(),
+        },
+    }
+}
+
+fn print_report(reviews: &[Review]) {
+    let section = |title: &str, filter: fn(&Outcome) -> bool| {
+        let count = reviews.iter().filter(|r| filter(&r.outcome)).count();
+        println!("\n{}", style(format!("{} ({})", title, count)).bold());
+    };
+
+    section("Matched", |o| matches!(o, Outcome::Matched { .. }));
+    for review in reviews {
+        if let Outcome::Matched { paper, by } = &review.outcome {
+            println!("  {}", review.path.display());
+            println!(
+                "      {} {}",
+                style(format!("by {}:", by)).dim(),
+                paper.metadata()
+            );
+        }
+    }
+
+    section("Ambiguous", |o| matches!(o, Outcome::Ambiguous { .. }));
+    for review in reviews {
+        if let Outcome::Ambiguous { candidates } = &review.outcome {
+            println!("  {}", review.path.display());
+            for candidate in candidates {
+                println!(
+                    "      [{:.2}] {}",
+                    candidate.score,
+                    candidate.paper.metadata()
+                );
+            }
+        }
+    }
+
+    section("Unmatched", |o| matches!(o, Outcome::Unmatched { .. }));
+    for review in reviews {
+        if let Outcome::Unmatched { reason } = &review.outcome {
+            println!("  {} {}", review.path.display(), style(reason).dim());
+        }
+    }
+    println!();
+}
+
+fn progress(what: &str, i: usize, n: usize) {
+    print!("\r{} {}/{}", what, i, n);
+    if i == n {
+        println!();
+    }
+    std::io::stdout().flush().ok();
+}
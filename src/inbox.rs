@@ -0,0 +1,97 @@
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use console::style;
+
+use crate::{
+    library::{inbox, Library},
+    xiv_config::Config,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Review the PDFs which `xivar watch` could not file automatically")]
+pub struct Inbox {
+    #[clap(subcommand)]
+    command: Option<InboxCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+enum InboxCommand {
+    /// List the queued PDFs with the papers found for them (default)
+    List,
+    /// File a queued PDF into your library as one of the papers found for it
+    Accept {
+        /// Number of the PDF in `xivar inbox list`
+        n: usize,
+
+        /// Number of the paper found for the PDF
+        #[clap(short, long, default_value = "1")]
+        candidate: usize,
+    },
+    /// Remove a PDF from the inbox without filing it
+    Drop {
+        /// Number of the PDF in `xivar inbox list`
+        n: usize,
+    },
+}
+
+impl Inbox {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let entries = inbox::read(&config.data_dir)?;
+        match self.command.as_ref().unwrap_or(&InboxCommand::List) {
+            InboxCommand::List => {
+                if entries.is_empty() {
+                    println!("Your inbox is empty.");
+                }
+                for (i, entry) in entries.iter().enumerate() {
+                    println!(
+                        "{:>3}  {}  {}",
+                        i + 1,
+                        style(entry.path.display()).bold(),
+                        style(format!("({}, {})", entry.reason, entry.time)).dim()
+                    );
+                    for (j, candidate) in entry.candidates.iter().enumerate() {
+                        let info = candidate.paper.metadata();
+                        println!(
+                            "       {}. [{:.2}] {} {} {}",
+                            j + 1,
+                            candidate.score,
+                            info,
+                            info.venue,
+                            info.year
+                        );
+                    }
+                }
+                Ok(())
+            }
+            InboxCommand::Accept { n, candidate } => {
+                let entry = get(&entries, *n)?;
+                let paper = match entry.candidates.get(candidate.wrapping_sub(1)) {
+                    Some(candidate) => candidate.paper.clone(),
+                    None => bail!("No paper {} found for {}.", candidate, entry.path.display()),
+                };
+                let mut lib = Library::open(&config)?;
+                println!("Filed {} as {}", entry.path.display(), paper.metadata());
+                lib.add(paper);
+                lib.save()?;
+                inbox::update(&config.data_dir, |entries| {
+                    entries.retain(|e| e.path != entry.path)
+                })
+            }
+            InboxCommand::Drop { n } => {
+                let entry = get(&entries, *n)?;
+                inbox::update(&config.data_dir, |entries| {
+                    entries.retain(|e| e.path != entry.path)
+                })?;
+                println!("Removed {} from your inbox.", entry.path.display());
+                Ok(())
+            }
+        }
+    }
+}
+
+fn get(entries: &[inbox::InboxEntry], n: usize) -> Result<&inbox::InboxEntry> {
+    match entries.get(n.wrapping_sub(1)) {
+        Some(entry) => Ok(entry),
+        None => bail!("No PDF {} in your inbox, see `xivar inbox list`.", n),
+    }
+}
@@ -1,9 +1,10 @@
 mod state;
 
 use crate::{
-    library::{lib_manager_fut, LibReq, LoadingResult},
+    library::{lib_manager_fut, AttachmentRole, LibReq, LoadingResult, LocalPaper},
     remotes::{self, FetchResult, Remote},
-    util::async_download_and_save,
+    template::Template,
+    util::{async_download_and_save, edit_in_editor, unique_path},
     xiv_config::Config,
     PaperInfo, PaperUrl, Query,
 };
@@ -11,7 +12,16 @@ use anyhow::Result;
 use console::style;
 use itertools::Itertools;
 
-use std::{io::Write, path::PathBuf, time::Duration};
+use std::{
+    io::{Read, Write},
+    os::unix::io::AsRawFd,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use termion::{clear, cursor, event::Key, input::TermRead, raw::IntoRawMode};
 use tokio::sync::watch;
 
@@ -33,16 +43,12 @@ pub async fn interactive(config: Config) -> Result<()> {
 
     let (shutdown_tx, _) = tokio::sync::broadcast::channel::<()>(1);
 
+    // Set while an external editor owns the terminal.
+    let stdin_paused = Arc::new(AtomicBool::new(false));
+    let paused = stdin_paused.clone();
     tokio::task::spawn_blocking(move || {
-        while let Some(key) = std::io::stdin().keys().next() {
-            if let Ok(key) = key {
-                if let Err(_) = stdin_tx.blocking_send(key) {
-                    // TODO
-                }
-                if Key::Ctrl('c') == key {
-                    break;
-                }
-            }
+        if let Err(err) = read_keys(stdin_tx, paused) {
+            log::error!("Could not read from terminal: {}", err);
         }
     });
 
@@ -74,7 +80,7 @@ pub async fn interactive(config: Config) -> Result<()> {
     ));
     let (loading_tx, mut loading_rx) = tokio::sync::mpsc::channel::<LoadingResult>(1);
     tokio::task::spawn(lib_manager_fut(
-        config.data_dir.clone(),
+        config.clone(),
         local_rx,
         shutdown_tx.subscribe(),
         loading_tx,
@@ -84,7 +90,7 @@ pub async fn interactive(config: Config) -> Result<()> {
     let mut total_remotes: usize = 2;
     loop {
         tokio::select! {
-            key = (&mut stdin_rx).recv() => {
+            key = stdin_rx.recv() => {
                 if let Some(key) = key {
                     log::info!("Pressed key {:?}", key);
 
@@ -105,8 +111,45 @@ pub async fn interactive(config: Config) -> Result<()> {
                                 shutdown_tx.send(())?;
                                 break;
                             },
-                            Action::Download(info, url) => {
-                                tokio::task::spawn(download_paper(config.paper_dir.clone(),info, url, local_tx.clone(), progress_tx.clone()));
+                            Action::UpdateLocal(paper) => {
+                                local_tx.send(LibReq::Update { paper }).await?;
+                                data.write_to_terminal(&mut stdout)?;
+                            },
+                            Action::EditNote(mut paper) => {
+                                stdin_paused.store(true, Ordering::SeqCst);
+                                write!(stdout, "{}{}", clear::All, cursor::Show)?;
+                                stdout.suspend_raw_mode()?;
+                                let note = tokio::task::block_in_place(|| edit_in_editor(&paper.note));
+                                stdout.activate_raw_mode()?;
+                                stdin_paused.store(false, Ordering::SeqCst);
+                                write!(stdout, "{}", clear::All)?;
+                                match note {
+                                    Ok(note) if note != paper.note => {
+                                        paper.note = note;
+                                        data.update_local(&paper);
+                                        local_tx.send(LibReq::Update { paper }).await?;
+                                    }
+                                    Ok(_) => {}
+                                    Err(err) => log::error!("Could not edit note: {}", err),
+                                }
+                                data.write_to_terminal(&mut stdout)?;
+                            },
+                            Action::Undo => {
+                                let (res_channel, res) = tokio::sync::oneshot::channel();
+                                local_tx.send(LibReq::Undo { res_channel }).await?;
+                                match res.await? {
+                                    Ok(msg) => log::info!("{}", msg),
+                                    Err(err) => log::error!("Could not undo: {}", err),
+                                }
+                                if !data.term().is_empty() {
+                                    remotes_fetched = 0;
+                                    query_tx.send(data.term().to_string())?;
+                                    data.clear_papers();
+                                }
+                                data.write_to_terminal(&mut stdout)?;
+                            },
+                            Action::Download(info, url, role) => {
+                                tokio::task::spawn(download_paper(config.paper_dir.clone(), config.filename.clone(), info, url, role, local_tx.clone(), progress_tx.clone()));
                             },
                             Action::FetchToClip(url) => {
                                 tokio::task::spawn(async move {
@@ -136,8 +179,10 @@ pub async fn interactive(config: Config) -> Result<()> {
                 }
             },
             load_res = loading_rx.recv() => {
-                if let Some(LoadingResult::Success) = load_res {
-                    total_remotes += 1
+                match load_res {
+                    Some(LoadingResult::Success) => total_remotes += 1,
+                    Some(LoadingResult::Failure(err)) => log::error!("Could not load library: {}", err),
+                    None => {}
                 }
             },
         }
@@ -161,6 +206,40 @@ pub async fn interactive(config: Config) -> Result<()> {
     Ok(())
 }
 
+/// Forwards key presses from the terminal until Ctrl-C is pressed.
+///
+/// The terminal is polled instead of blocking on stdin, so that no keys are
+/// taken away from an editor while `paused` is set.
+fn read_keys(stdin_tx: tokio::sync::mpsc::Sender<Key>, paused: Arc<AtomicBool>) -> Result<()> {
+    let tty = termion::get_tty()?;
+    let mut buffer = [0u8; 64];
+    loop {
+        if paused.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(100));
+            continue;
+        }
+        let mut fd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a single valid pollfd and the descriptor outlives the call.
+        let ready = unsafe { libc::poll(&mut fd, 1, 100) };
+        if ready <= 0 || paused.load(Ordering::SeqCst) {
+            continue;
+        }
+        let n = (&tty).read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        for key in (&buffer[..n]).keys().flatten() {
+            if stdin_tx.blocking_send(key).is_err() || key == Key::Ctrl('c') {
+                return Ok(());
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 enum ProgressRequest {
     Start(String, u16),
@@ -169,8 +248,10 @@ enum ProgressRequest {
 
 async fn download_paper(
     paper_dir: PathBuf,
+    filename: Template,
     info: PaperInfo,
     url: PaperUrl,
+    role: AttachmentRole,
     local_tx: tokio::sync::mpsc::Sender<LibReq>,
     progress_tx: tokio::sync::mpsc::Sender<ProgressRequest>,
 ) -> Result<()> {
@@ -180,10 +261,14 @@ async fn download_paper(
         .await
         .unwrap();
     log::info!("Starting to download paper at {:?}", url);
-    let dest = paper_dir
-        .join(info.default_filename())
-        .with_extension("pdf");
-    let paper = async_download_and_save(info, url, &dest).await?;
+    let paper = LocalPaper::new(info.clone(), vec![], vec![]);
+    let dest = unique_path(&paper_dir.join(filename.file_path(&paper, "pdf")), |p| {
+        p.exists()
+    });
+    if let Some(dir) = dest.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let paper = async_download_and_save(info, url, role, &dest).await?;
     log::info!("Finished downloading paper!");
     local_tx.send(LibReq::Save { paper }).await.unwrap();
     progress_tx
@@ -307,7 +392,10 @@ async fn fetch_manager<R: Remote + std::marker::Send + Clone>(
 pub enum Action {
     UpdateSearch,
     FetchToClip(PaperUrl),
-    Download(PaperInfo, PaperUrl),
+    Download(PaperInfo, PaperUrl, AttachmentRole),
+    UpdateLocal(LocalPaper),
+    EditNote(LocalPaper),
+    Undo,
     Reprint,
     Quit,
 }
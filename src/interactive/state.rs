@@ -4,7 +4,10 @@ use itertools::Itertools;
 use std::fmt::Display;
 use termion::{clear, cursor, event::Key};
 
-use crate::{merge_to_papers, Paper, PaperHit};
+use crate::{
+    library::{AttachmentRole, LocalPaper},
+    merge_to_papers, Paper, PaperHit,
+};
 
 use super::Action;
 
@@ -88,6 +91,7 @@ impl StateData {
                 }
                 None
             }
+            (Key::Ctrl('z'), _) => Some(Action::Undo),
             (Key::Ctrl('s'), _) => {
                 self.reset();
                 Some(Action::Reprint)
@@ -115,7 +119,9 @@ impl StateData {
                 let hit = selected.0.first().unwrap();
                 match hit {
                     PaperHit::Local(paper) => {
-                        open::that(&paper.location).unwrap();
+                        if let Some(location) = paper.location() {
+                            open::that(location).unwrap();
+                        }
                     }
                     PaperHit::Dblp(ref paper) => {
                         open::that(paper.ee.raw()).unwrap();
@@ -140,10 +146,100 @@ impl StateData {
                     None
                 }
             }
+            (Key::Char('t'), State::SelectedHit { index, hit })
+                if matches!(hit.as_ref(), PaperHit::Local(_)) =>
+            {
+                self.state = State::Tagging {
+                    index,
+                    hit,
+                    input: String::new(),
+                };
+                Some(Action::Reprint)
+            }
+            (Key::Char('n'), State::SelectedHit { hit, .. })
+                if matches!(hit.as_ref(), PaperHit::Local(_)) =>
+            {
+                if let PaperHit::Local(paper) = *hit {
+                    Some(Action::EditNote(paper))
+                } else {
+                    None
+                }
+            }
+            (Key::Char(c @ ('r' | 'p' | '+' | '-')), State::SelectedHit { index, hit })
+                if matches!(hit.as_ref(), PaperHit::Local(_)) =>
+            {
+                if let PaperHit::Local(mut paper) = *hit {
+                    match c {
+                        'r' => paper.status = paper.status.next(),
+                        'p' => paper.priority = paper.priority.next(),
+                        '+' => paper.rate_up(),
+                        _ => paper.rate_down(),
+                    }
+                    self.replace_local(&paper);
+                    self.state = State::SelectedHit {
+                        index,
+                        hit: Box::new(PaperHit::Local(paper.clone())),
+                    };
+                    Some(Action::UpdateLocal(paper))
+                } else {
+                    None
+                }
+            }
+            (Key::Char('\n'), State::Tagging { index, hit, input }) => {
+                if let PaperHit::Local(mut paper) = *hit {
+                    for word in input.split_whitespace() {
+                        match word.strip_prefix('-') {
+                            Some(tag) => paper.remove_tag(tag),
+                            None => paper.add_tag(word.trim_start_matches('+')),
+                        };
+                    }
+                    self.replace_local(&paper);
+                    self.state = State::SelectedHit {
+                        index,
+                        hit: Box::new(PaperHit::Local(paper.clone())),
+                    };
+                    Some(Action::UpdateLocal(paper))
+                } else {
+                    None
+                }
+            }
+            (
+                Key::Char(c),
+                State::Tagging {
+                    index,
+                    hit,
+                    mut input,
+                },
+            ) => {
+                input.push(c);
+                self.state = State::Tagging { index, hit, input };
+                Some(Action::Reprint)
+            }
+            (
+                Key::Backspace,
+                State::Tagging {
+                    index,
+                    hit,
+                    mut input,
+                },
+            ) => {
+                input.pop();
+                self.state = State::Tagging { index, hit, input };
+                Some(Action::Reprint)
+            }
+            (Key::Esc, State::Tagging { index, hit, .. }) => {
+                self.state = State::SelectedHit { index, hit };
+                Some(Action::Reprint)
+            }
             (Key::Char(s), State::SelectedHit { index: _, hit }) => {
                 match hit.as_ref() {
                     PaperHit::Local(paper) => {
-                        open::that(&paper.location).unwrap();
+                        let attachment = s
+                            .to_digit(10)
+                            .and_then(|d| paper.attachments.get((d as usize).checked_sub(1)?));
+                        if let Some(attachment) = attachment {
+                            open::that(&attachment.path).unwrap();
+                        }
                     }
                     PaperHit::Dblp(paper) => {
                         if s == '1' {
@@ -161,6 +257,7 @@ impl StateData {
                             return Some(Action::Download(
                                 paper.metadata().clone(),
                                 paper.download_url(),
+                                AttachmentRole::Arxiv(paper.version()),
                             ));
                         }
                         if s == '2' {
@@ -254,11 +351,22 @@ impl StateData {
                 //     write_line(writer, height - 2, summary);
                 // }
                 match hit.as_ref() {
-                    PaperHit::Local(paper) => write_line(
-                        writer,
-                        2,
-                        &format!("Select action: (1) open {:?}", paper.location),
-                    ),
+                    PaperHit::Local(paper) => {
+                        let attachments = paper
+                            .attachments
+                            .iter()
+                            .enumerate()
+                            .map(|(i, a)| format!("({}) open {}", i + 1, a))
+                            .join("  ");
+                        write_line(
+                            writer,
+                            2,
+                            &format!(
+                                "Select action: {}  (t) edit tags  (n) edit note  (r) status: {}  (p) priority: {}  (+/-) rating",
+                                attachments, paper.status, paper.priority
+                            ),
+                        )
+                    }
                     PaperHit::Dblp(paper) => write_line(
                         writer,
                         2,
@@ -273,12 +381,31 @@ impl StateData {
                     }
                 }
             }
+            State::Tagging {
+                index: _,
+                hit,
+                input,
+            } => {
+                if let PaperHit::Local(paper) = hit.as_ref() {
+                    write_line(
+                        writer,
+                        2,
+                        &format!(
+                            "Tags [{}] (+add -remove): {}",
+                            paper.tags.join(", "),
+                            style(input).black().on_white()
+                        ),
+                    )
+                }
+            }
         }
 
         // Papers
         for (i, paper) in self.papers().iter().enumerate().take((height - 5) as usize) {
             match self.state {
-                State::Scrolling(j) | State::SelectedHit { index: j, hit: _ }
+                State::Scrolling(j)
+                | State::SelectedHit { index: j, hit: _ }
+                | State::Tagging { index: j, .. }
                     if j as usize == i =>
                 {
                     write!(
@@ -305,6 +432,29 @@ impl StateData {
         Ok(())
     }
 
+    /// Replaces all hits of `paper`, including the selected one, by its updated version.
+    pub fn update_local(&mut self, paper: &LocalPaper) {
+        self.replace_local(paper);
+        if let State::SelectedHit { hit, .. } = &mut self.state {
+            if let PaperHit::Local(local) = hit.as_mut() {
+                if local.metadata() == paper.metadata() {
+                    *local = paper.clone();
+                }
+            }
+        }
+    }
+
+    /// Replaces all hits of `paper` by its updated version.
+    fn replace_local(&mut self, paper: &LocalPaper) {
+        for hit in self.papers.iter_mut().flat_map(|p| p.0.iter_mut()) {
+            if let PaperHit::Local(local) = hit {
+                if local.metadata() == paper.metadata() {
+                    *local = paper.clone();
+                }
+            }
+        }
+    }
+
     fn reset(&mut self) {
         self.term.clear();
         self.papers.clear();
@@ -317,7 +467,15 @@ pub enum State {
     Idle,
     Searching,
     Scrolling(u16),
-    SelectedHit { index: u16, hit: Box<PaperHit> },
+    SelectedHit {
+        index: u16,
+        hit: Box<PaperHit>,
+    },
+    Tagging {
+        index: u16,
+        hit: Box<PaperHit>,
+        input: String,
+    },
 }
 
 fn write_line<I: Display, W: std::io::Write>(writer: &mut W, line: u16, item: &I) {
@@ -0,0 +1,148 @@
+use std::{
+    fmt::{Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+/// A local file belonging to a paper.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub path: PathBuf,
+    pub role: AttachmentRole,
+    /// Identifies the file after it has been moved, `None` until the file has been hashed.
+    pub hash: Option<ContentHash>,
+}
+
+impl Attachment {
+    pub fn new<P: Into<PathBuf>>(path: P, role: AttachmentRole) -> Self {
+        Attachment {
+            path: path.into(),
+            role,
+            hash: None,
+        }
+    }
+
+    pub fn exists(&self) -> bool {
+        Path::new(&self.path).exists()
+    }
+
+    /// Hashes the file if it has not been hashed yet. Returns `true` if the hash was added.
+    pub fn update_hash(&mut self) -> Result<bool> {
+        if self.hash.is_some() || !self.exists() {
+            return Ok(false);
+        }
+        self.hash = Some(ContentHash::of_file(&self.path)?);
+        Ok(true)
+    }
+}
+
+impl Display for Attachment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = self
+            .path
+            .file_name()
+            .unwrap_or(self.path.as_os_str())
+            .to_string_lossy();
+        write!(f, "{} {}", self.role, name)
+    }
+}
+
+/// Size and SHA-1 digest of the content of a file.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentHash {
+    pub size: u64,
+    pub sha1: [u8; 20],
+}
+
+impl ContentHash {
+    pub fn of_file(path: &Path) -> Result<Self> {
+        let mut file =
+            fs::File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+        let mut hasher = Sha1::new();
+        let size = io::copy(&mut file, &mut hasher)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        Ok(ContentHash {
+            size,
+            sha1: hasher.finalize().into(),
+        })
+    }
+
+    pub fn of_bytes(bytes: &[u8]) -> Self {
+        ContentHash {
+            size: bytes.len() as u64,
+            sha1: Sha1::digest(bytes).into(),
+        }
+    }
+
+    /// Parses the lowercase hex digest written by `Display`.
+    pub fn from_hex(size: u64, hex: &str) -> Option<Self> {
+        if hex.len() != 40 || !hex.is_ascii() {
+            return None;
+        }
+        let mut sha1 = [0u8; 20];
+        for (i, byte) in sha1.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+        }
+        Some(ContentHash { size, sha1 })
+    }
+}
+
+impl Display for ContentHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for byte in self.sha1 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// What an attachment is, written as `main`, `supplement`, `slides`, `arxiv` or `arxiv-v<N>`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentRole {
+    /// The published version of the paper.
+    Main,
+    Supplement,
+    Slides,
+    /// A preprint from arXiv, with its version if known.
+    Arxiv(Option<u32>),
+}
+
+impl Display for AttachmentRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachmentRole::Main => write!(f, "main"),
+            AttachmentRole::Supplement => write!(f, "supplement"),
+            AttachmentRole::Slides => write!(f, "slides"),
+            AttachmentRole::Arxiv(None) => write!(f, "arxiv"),
+            AttachmentRole::Arxiv(Some(version)) => write!(f, "arxiv-v{}", version),
+        }
+    }
+}
+
+impl FromStr for AttachmentRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "main" => AttachmentRole::Main,
+            "supplement" => AttachmentRole::Supplement,
+            "slides" => AttachmentRole::Slides,
+            "arxiv" => AttachmentRole::Arxiv(None),
+            other => match other
+                .strip_prefix("arxiv-v")
+                .and_then(|v| v.parse::<u32>().ok())
+            {
+                Some(version) => AttachmentRole::Arxiv(Some(version)),
+                None => bail!(
+                    "unknown attachment role {}, expected main, supplement, slides, arxiv or arxiv-v<N>",
+                    s
+                ),
+            },
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use super::{bincode_store, LocalPaper};
+
+/// Number of snapshots kept if not configured otherwise.
+pub const DEFAULT_KEEP: usize = 10;
+
+/// A snapshot of the library in `<data_dir>/backups`, taken after a save.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    /// Timestamp of the snapshot, also part of its file name.
+    pub id: String,
+    pub path: PathBuf,
+}
+
+impl Backup {
+    /// Reads the papers of the snapshot, upgrading older layouts if necessary.
+    pub fn read(&self) -> Result<Vec<LocalPaper>> {
+        let buffer = fs::read(&self.path)
+            .with_context(|| format!("could not read backup: {}", self.path.display()))?;
+        let (_, papers) = bincode_store::decode(&buffer)
+            .with_context(|| format!("could not load backup: {}", self.path.display()))?;
+        Ok(papers)
+    }
+}
+
+fn backup_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("backups")
+}
+
+/// All snapshots, newest first.
+pub fn list(data_dir: &Path) -> Result<Vec<Backup>> {
+    let dir = backup_dir(data_dir);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut backups: Vec<Backup> = fs::read_dir(&dir)
+        .with_context(|| format!("could not read backups: {}", dir.display()))?
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let id = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix("lib-")?
+                .strip_suffix(".db")?
+                .to_owned();
+            Some(Backup { id, path })
+        })
+        .collect();
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(backups)
+}
+
+/// Writes a snapshot of `papers` and removes the oldest ones, so that at most `keep` remain.
+pub fn write(data_dir: &Path, papers: &[LocalPaper], keep: usize) -> Result<()> {
+    let dir = backup_dir(data_dir);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("unable to create backup directory: {}", dir.display()))?;
+
+    let id = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+    let mut path = dir.join(format!("lib-{}.db", id));
+    // several saves within the same millisecond
+    for n in 1.. {
+        if !path.exists() {
+            break;
+        }
+        path = dir.join(format!("lib-{}-{}.db", id, n));
+    }
+    fs::write(&path, bincode_store::encode(papers)?)
+        .with_context(|| format!("could not write backup: {}", path.display()))?;
+
+    for backup in list(data_dir)?.iter().skip(keep) {
+        fs::remove_file(&backup.path)
+            .with_context(|| format!("could not remove backup: {}", backup.path.display()))?;
+    }
+    Ok(())
+}
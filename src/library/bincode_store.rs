@@ -0,0 +1,214 @@
+use std::{
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Context, Result};
+use bincode::Options;
+use tempfile::{NamedTempFile, PersistError};
+
+use super::{migrations, store::Store, ContentHash, Library, LibraryVersion, LocalPaper};
+
+/// The whole library serialized with bincode into a single file `lib.db`.
+#[derive(Debug)]
+pub struct BincodeStore {
+    data_dir: PathBuf,
+    stamp: Option<Stamp>,
+}
+
+/// Modification time and content of `lib.db` when it was last read or written by us. The time
+/// alone misses writes of the same size within its granularity.
+type Stamp = (SystemTime, ContentHash);
+
+impl BincodeStore {
+    pub fn new<P: Into<PathBuf>>(data_dir: P) -> Self {
+        BincodeStore {
+            data_dir: data_dir.into(),
+            stamp: None,
+        }
+    }
+
+    fn get_path(&self) -> PathBuf {
+        self.data_dir.join("lib.db")
+    }
+
+    /// The stamp of `lib.db` with `content`, which was just read or written.
+    fn stamp(&self, content: &[u8]) -> Result<Stamp> {
+        let modified = fs::metadata(self.get_path())?.modified()?;
+        Ok((modified, ContentHash::of_bytes(content)))
+    }
+}
+
+impl Store for BincodeStore {
+    fn load(&mut self) -> Result<Vec<LocalPaper>> {
+        let path = self.get_path();
+
+        let buffer = match fs::read(&path) {
+            Ok(buffer) => buffer,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                fs::create_dir_all(&self.data_dir).with_context(|| {
+                    format!("unable to create data directory: {}", path.display())
+                })?;
+                self.stamp = None;
+                return Ok(Vec::new());
+            }
+            Err(e) => {
+                Err(e).with_context(|| format!("could not read from store: {}", path.display()))?
+            }
+        };
+
+        let (version, papers) =
+            decode(&buffer).with_context(|| format!("could not load store: {}", path.display()))?;
+
+        if version != Library::CURRENT_VERSION {
+            let backup = migrations::backup(&path, version)?;
+            log::warn!(
+                "Migrating library from version {} to {}. Backup saved to {}",
+                version.0,
+                Library::CURRENT_VERSION.0,
+                backup.display()
+            );
+            self.save(&papers)?;
+        } else {
+            self.stamp = Some(self.stamp(&buffer)?);
+        }
+
+        Ok(papers)
+    }
+
+    fn save(&mut self, papers: &[LocalPaper]) -> Result<()> {
+        let buffer = encode(papers)?;
+
+        let mut file = NamedTempFile::new_in(&self.data_dir).with_context(|| {
+            format!(
+                "could not create temporary store in: {}",
+                self.data_dir.display()
+            )
+        })?;
+
+        let _ = file.as_file().set_len(buffer.len() as _);
+        file.write_all(&buffer).with_context(|| {
+            format!(
+                "could not write to temporary store: {}",
+                file.path().display()
+            )
+        })?;
+
+        let path = self.get_path();
+        persist(file, &path)
+            .with_context(|| format!("could not replace store: {}", path.display()))?;
+        self.stamp = Some(self.stamp(&buffer)?);
+
+        Ok(())
+    }
+
+    fn changed(&self) -> Result<bool> {
+        let meta = match fs::metadata(self.get_path()) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(self.stamp.is_some()),
+            Err(e) => return Err(e.into()),
+        };
+        match &self.stamp {
+            None => Ok(true),
+            Some((modified, hash)) if *modified == meta.modified()? && hash.size == meta.len() => {
+                // only read the file if its time and size cannot tell
+                Ok(ContentHash::of_file(&self.get_path())? != *hash)
+            }
+            Some(_) => Ok(true),
+        }
+    }
+}
+
+/// Serializes `papers` in the current layout, prefixed by the store version.
+pub fn encode(papers: &[LocalPaper]) -> Result<Vec<u8>> {
+    (|| -> bincode::Result<_> {
+        let version_size = bincode::serialized_size(&Library::CURRENT_VERSION)?;
+        let papers_size = bincode::serialized_size(papers)?;
+
+        let mut buffer = Vec::with_capacity((version_size + papers_size) as _);
+
+        bincode::serialize_into(&mut buffer, &Library::CURRENT_VERSION)?;
+        bincode::serialize_into(&mut buffer, papers)?;
+
+        Ok(buffer)
+    })()
+    .context("could not serialize store")
+}
+
+/// Deserializes a buffer written by `encode` of this or any previous version of xivar.
+pub fn decode(buffer: &[u8]) -> Result<(LibraryVersion, Vec<LocalPaper>)> {
+    let deserializer = &mut bincode::options().with_fixint_encoding();
+
+    let version_size = deserializer
+        .serialized_size(&Library::CURRENT_VERSION)
+        .unwrap() as _;
+
+    if buffer.len() < version_size {
+        bail!("store is truncated");
+    }
+    let (buffer_version, buffer_papers) = buffer.split_at(version_size);
+
+    let version: LibraryVersion = deserializer
+        .deserialize(buffer_version)
+        .context("could not deserialize store version")?;
+
+    if version.0 > Library::CURRENT_VERSION.0 {
+        bail!(
+            "store was written by a newer version of xivar, got={}, supported={}",
+            version.0,
+            Library::CURRENT_VERSION.0,
+        );
+    }
+
+    let papers = migrations::load(version, buffer_papers).context("could not deserialize store")?;
+    Ok((version, papers))
+}
+
+fn persist<P: AsRef<Path>>(file: NamedTempFile, path: P) -> Result<(), PersistError> {
+    file.persist(&path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::{PaperInfo, PaperTitle, Venue};
+
+    fn paper(title: &str) -> LocalPaper {
+        let info = PaperInfo {
+            id: None,
+            title: PaperTitle::new(title.to_owned()),
+            venue: Venue::Conf("SODA".to_owned()),
+            authors: vec!["Nicole Megow".to_owned()],
+            year: "2022".to_owned(),
+            summary: None,
+        };
+        LocalPaper::new(info, vec![], vec![])
+    }
+
+    #[test]
+    fn notice_writes_of_the_same_size_and_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ours = BincodeStore::new(dir.path());
+        let mut theirs = BincodeStore::new(dir.path());
+        ours.save(&[paper("Routing")]).unwrap();
+        assert!(theirs.changed().unwrap());
+        theirs.load().unwrap();
+        assert!(!theirs.changed().unwrap());
+
+        let path = dir.path().join("lib.db");
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        ours.save(&[paper("Packing")]).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert!(theirs.changed().unwrap());
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use deunicode::deunicode;
+
+use super::LocalPaper;
+use crate::{
+    template::{significant_words, surname, Template},
+    PaperInfo,
+};
+
+/// Citation key `<surname><year><title word>` of the first author and the first significant
+/// word of the title in lowercase ASCII, e.g. `lindermayr2022learning`.
+pub fn generate(info: &PaperInfo) -> String {
+    let surname = info
+        .authors
+        .first()
+        .and_then(|author| surname(author))
+        .map(ascii_word)
+        .unwrap_or_default();
+    let year: String = info.year.chars().filter(char::is_ascii_digit).collect();
+    let word = significant_words(&info.title)
+        .map(ascii_word)
+        .next()
+        .unwrap_or_default();
+
+    let key = format!("{}{}{}", surname, year, word);
+    if key.is_empty() {
+        "paper".to_owned()
+    } else {
+        key
+    }
+}
+
+/// Citation keys of `papers` after `template`, with every field in lowercase ASCII. Papers whose
+/// key already follows the template keep it, and all others with the same key are told apart by
+/// the next free suffix, so that the keys do not change as long as they are saved. A key follows
+/// the template if it is the rendered key, or that key with the suffix of one of the papers
+/// sharing it, and is not the rendered key of another paper.
+pub fn generate_all(papers: &[LocalPaper], template: &Template) -> Result<Vec<String>> {
+    let mut bases = vec![];
+    for paper in papers {
+        let key = template.render(paper, ascii_word);
+        let key = if key.is_empty() {
+            "paper".to_owned()
+        } else {
+            key
+        };
+        if !is_valid(&key) {
+            bail!(
+                "citation key template {} gives the invalid key {}, keys may only contain ASCII letters, digits, `-`, `_` and `.`",
+                template,
+                key
+            );
+        }
+        bases.push(key);
+    }
+
+    let mut shared: HashMap<&str, usize> = HashMap::new();
+    for base in &bases {
+        *shared.entry(base.as_str()).or_default() += 1;
+    }
+    let mut taken = HashSet::new();
+    let mut keys: Vec<Option<String>> = papers
+        .iter()
+        .zip(&bases)
+        .map(|(paper, base)| {
+            let fits = match paper.citekey.strip_prefix(base.as_str()) {
+                Some("") => true,
+                Some(rest) => {
+                    suffix_index(rest).is_some_and(|n| n < shared[base.as_str()])
+                        && !shared.contains_key(paper.citekey.as_str())
+                }
+                None => false,
+            };
+            (fits && taken.insert(paper.citekey.clone())).then(|| paper.citekey.clone())
+        })
+        .collect();
+    for (key, base) in keys.iter_mut().zip(&bases) {
+        if key.is_none() {
+            let new = disambiguate(base, |key| taken.contains(key));
+            taken.insert(new.clone());
+            *key = Some(new);
+        }
+    }
+    Ok(keys.into_iter().flatten().collect())
+}
+
+/// Whether `key` can be used as a file name, i.e. is not empty and only consists of ASCII letters,
+/// digits, `-`, `_` and `.` without a leading dot.
+pub fn is_valid(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('.')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Appends `a`, `b`, …, `z`, `aa`, … to `key` until `taken` returns `false`.
+pub fn disambiguate(key: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(key) {
+        return key.to_owned();
+    }
+    (0..)
+        .map(|n| format!("{}{}", key, suffix(n)))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+fn suffix(mut n: usize) -> String {
+    let mut suffix = vec![];
+    loop {
+        suffix.push(b'a' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    suffix.reverse();
+    String::from_utf8(suffix).unwrap()
+}
+
+/// Inverse of `suffix`, `None` if `suffix` is not one.
+fn suffix_index(suffix: &str) -> Option<usize> {
+    if suffix.is_empty() || !suffix.bytes().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    suffix
+        .bytes()
+        .try_fold(0usize, |n, c| {
+            n.checked_mul(26)?.checked_add((c - b'a') as usize + 1)
+        })
+        .map(|n| n - 1)
+}
+
+/// Transliterates `word` to lowercase ASCII and drops everything but letters and digits.
+fn ascii_word(word: &str) -> String {
+    deunicode(word)
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PaperTitle, Venue};
+
+    fn paper(title: &str) -> LocalPaper {
+        let info = PaperInfo {
+            id: None,
+            title: PaperTitle::new(title.to_owned()),
+            venue: Venue::Conf("SODA".to_owned()),
+            authors: vec!["Nicole Megow".to_owned()],
+            year: "2022".to_owned(),
+            summary: None,
+        };
+        LocalPaper::new(info, vec![], vec![])
+    }
+
+    #[test]
+    fn suffixes() {
+        for n in [0, 1, 25, 26, 27, 52, 701, 702, 12345] {
+            assert_eq!(suffix_index(&suffix(n)), Some(n));
+        }
+        assert_eq!(suffix_index(""), None);
+        assert_eq!(suffix_index("a1"), None);
+        assert_eq!(suffix_index("A"), None);
+    }
+
+    #[test]
+    fn keys_must_follow_the_template() {
+        let template: Template = "{author}{year}".parse().unwrap();
+        let mut papers = vec![paper("Scheduling"), paper("Routing")];
+        papers[0].citekey = "megow2022ab".to_owned();
+        papers[1].citekey = "megow2021".to_owned();
+        // the suffix of `megow2022ab` is below the size of the library, but not of two papers
+        for i in 0..30 {
+            let mut other = paper("Packing");
+            other.metadata.authors = vec![format!("Author{}", i)];
+            papers.push(other);
+        }
+        let keys = generate_all(&papers, &template).unwrap();
+        assert_eq!(keys[..2], ["megow2022", "megow2022a"]);
+
+        // a suffixed key must not take the key of another author
+        let template: Template = "{author}".parse().unwrap();
+        let mut papers = vec![paper("Scheduling"), paper("Routing")];
+        papers[0].citekey = "megowa".to_owned();
+        papers[1].metadata.authors = vec!["Anna Megowa".to_owned()];
+        let keys = generate_all(&papers, &template).unwrap();
+        assert_eq!(keys, ["megow", "megowa"]);
+    }
+
+    #[test]
+    fn keys_are_stable() {
+        let template: Template = "{author}{year}".parse().unwrap();
+        let mut papers = vec![paper("Scheduling"), paper("Routing"), paper("Packing")];
+        let keys = generate_all(&papers, &template).unwrap();
+        assert_eq!(keys, ["megow2022", "megow2022a", "megow2022b"]);
+        for (paper, key) in papers.iter_mut().zip(keys) {
+            paper.citekey = key;
+        }
+
+        // saved keys do not depend on the order or on other papers
+        papers.remove(0);
+        papers.reverse();
+        papers.push(paper("Covering"));
+        let keys = generate_all(&papers, &template).unwrap();
+        assert_eq!(keys, ["megow2022b", "megow2022a", "megow2022"]);
+
+        // keys of another scheme are replaced
+        let template: Template = "{author}{year}{title}".parse().unwrap();
+        let keys = generate_all(&papers, &template).unwrap();
+        assert_eq!(
+            keys,
+            ["megow2022packing", "megow2022routing", "megow2022covering"]
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::{
+    journal::{Change, OperationKind},
+    Library, LocalPaper,
+};
+use crate::{template::surname, PaperInfo};
+
+impl Library {
+    /// Groups of papers which are likely the same work, e.g. an arXiv preprint and its published
+    /// version. Papers are grouped if they share an identifier or a file, or if they have the same
+    /// title and at least one common author. Titles like "Introduction" or "Editorial" are common,
+    /// so papers without authors need the same title and year.
+    pub fn duplicates(&self) -> Vec<Vec<LocalPaper>> {
+        let mut sets = DisjointSets::new(self.papers.len());
+
+        let mut by_id = HashMap::new();
+        let mut by_hash = HashMap::new();
+        let mut by_title: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, paper) in self.papers.iter().enumerate() {
+            if let Some(id) = &paper.metadata().id {
+                if let Some(&j) = by_id.get(id) {
+                    sets.union(i, j);
+                } else {
+                    by_id.insert(id.clone(), i);
+                }
+            }
+            for hash in paper.attachments.iter().filter_map(|a| a.hash) {
+                if let Some(&j) = by_hash.get(&hash) {
+                    sets.union(i, j);
+                } else {
+                    by_hash.insert(hash, i);
+                }
+            }
+            by_title
+                .entry(title_key(paper.metadata()))
+                .or_default()
+                .push(i);
+        }
+
+        for positions in by_title.values() {
+            for (k, &i) in positions.iter().enumerate() {
+                let info = self.papers[i].metadata();
+                let authors = author_keys(info);
+                for &j in &positions[k + 1..] {
+                    let other = self.papers[j].metadata();
+                    let others = author_keys(other);
+                    let same = if authors.is_empty() || others.is_empty() {
+                        !info.year.is_empty() && info.year == other.year
+                    } else {
+                        !authors.is_disjoint(&others)
+                    };
+                    if same {
+                        sets.union(i, j);
+                    }
+                }
+            }
+        }
+
+        let mut clusters: BTreeMap<usize, Vec<LocalPaper>> = BTreeMap::new();
+        for (i, paper) in self.papers.iter().enumerate() {
+            clusters
+                .entry(sets.find(i))
+                .or_default()
+                .push(paper.clone());
+        }
+        clusters
+            .into_values()
+            .filter(|cluster| cluster.len() > 1)
+            .collect()
+    }
+
+    /// Replaces the papers of `cluster` by a single entry with the metadata `keep`, which has the
+    /// files, links, tags, collections and notes of all of them.
+    pub fn merge_duplicates(&mut self, keep: &PaperInfo, cluster: &[PaperInfo]) -> LocalPaper {
+        let (merged, kept): (Vec<LocalPaper>, Vec<LocalPaper>) = self
+            .papers
+            .drain(..)
+            .partition(|p| cluster.contains(p.metadata()));
+        self.papers = kept;
+        let mut changes: Vec<Change> = vec![];
+        for paper in &merged {
+            self.forget(paper.metadata());
+            changes.push(Change {
+                before: Some(paper.clone()),
+                after: None,
+            });
+        }
+
+        // The entry with the chosen metadata goes first, so that its settings win.
+        let mut merged = merged;
+        merged.sort_by_key(|p| p.metadata() != keep);
+        let mut papers = merged.into_iter();
+        let mut survivor = papers.next().expect("cluster of existing papers");
+        survivor.metadata = keep.clone();
+        for mut paper in papers {
+            // A file shared by several entries keeps its role in the chosen one.
+            paper
+                .attachments
+                .retain(|a| !survivor.attachments.iter().any(|s| s.path == a.path));
+            // Keep the identifier and abstract of the other versions if the chosen one has none,
+            // e.g. so that the arXiv preprint is recognized again.
+            let info = &mut survivor.metadata;
+            if info.id.is_none() {
+                info.id = paper.metadata().id.clone();
+            }
+            if info.summary.is_none() {
+                info.summary = paper.metadata().summary.clone();
+            }
+            let note = if survivor.note.is_empty() || paper.note.is_empty() {
+                None
+            } else if survivor.note.trim() != paper.note.trim() {
+                Some(format!("{}\n\n{}", survivor.note.trim_end(), paper.note))
+            } else {
+                None
+            };
+            survivor.absorb(paper);
+            if let Some(note) = note {
+                survivor.note = note;
+            }
+        }
+
+        self.touch(survivor.metadata());
+        self.papers.push(survivor.clone());
+        changes.push(Change {
+            before: None,
+            after: Some(survivor.clone()),
+        });
+        self.record(OperationKind::Merge, changes);
+        self.rebuild_index();
+        self.modified = true;
+        survivor
+    }
+}
+
+/// Lowercase title without punctuation.
+fn title_key(info: &PaperInfo) -> String {
+    info.title
+        .normalized()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect()
+}
+
+/// Lowercase last names, ignoring the numbers DBLP appends to homonymous authors.
+fn author_keys(info: &PaperInfo) -> HashSet<String> {
+    info.authors
+        .iter()
+        .filter_map(|name| surname(name))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Union-find over positions.
+struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        DisjointSets {
+            parents: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, i: usize, j: usize) {
+        let (a, b) = (self.find(i), self.find(j));
+        if a != b {
+            self.parents[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        library::{Attachment, AttachmentRole, Backend},
+        ArxivIdentifier, Identifier, PaperTitle, Venue,
+    };
+
+    fn paper(title: &str, authors: &[&str], year: &str) -> LocalPaper {
+        let info = PaperInfo {
+            id: None,
+            title: PaperTitle::new(title.to_owned()),
+            venue: Venue::Conf("SODA".to_owned()),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            year: year.to_owned(),
+            summary: None,
+        };
+        LocalPaper::new(info, vec![], vec![])
+    }
+
+    fn titles(groups: &[Vec<LocalPaper>]) -> Vec<Vec<String>> {
+        groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|p| format!("{} {}", p.metadata().title, p.metadata().year))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn disjoint_sets() {
+        let mut sets = DisjointSets::new(6);
+        sets.union(4, 2);
+        sets.union(2, 5);
+        sets.union(1, 3);
+        assert_eq!(sets.find(5), 2);
+        assert_eq!(sets.find(4), 2);
+        assert_eq!(sets.find(3), 1);
+        assert_eq!(sets.find(0), 0);
+        sets.union(5, 3);
+        assert!((1..6).all(|i| sets.find(i) == 1));
+    }
+
+    #[test]
+    fn group_transitively() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["preprint.pdf", "published.pdf"] {
+            std::fs::write(dir.path().join(name), "same content").unwrap();
+        }
+        let mut lib = Library::open_with(Backend::Bincode, &dir.path().join("data")).unwrap();
+
+        let mut preprint = paper("Online Algorithms", &["Nicole Megow"], "2021");
+        preprint.metadata.id = Some(Identifier::Arxiv(
+            ArxivIdentifier::parse_string("2101.01234").unwrap(),
+        ));
+        preprint.attach(Attachment::new(
+            dir.path().join("preprint.pdf"),
+            AttachmentRole::Arxiv(None),
+        ));
+        // the same file under another name and title
+        let mut published = paper("Learning-Augmented Online Algorithms", &[], "2022");
+        published.attach(Attachment::new(
+            dir.path().join("published.pdf"),
+            AttachmentRole::Main,
+        ));
+        // the same title and a common author
+        let talk = paper("Online Algorithms", &["N. Megow", "B. Simon"], "2020");
+        for p in [preprint, published, talk, paper("Other", &[], "2021")] {
+            lib.add(p);
+        }
+
+        assert_eq!(
+            titles(&lib.duplicates()),
+            [[
+                "Online Algorithms 2021",
+                "Learning-Augmented Online Algorithms 2022",
+                "Online Algorithms 2020"
+            ]]
+        );
+    }
+
+    #[test]
+    fn common_titles_without_authors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut lib = Library::open_with(Backend::Bincode, dir.path()).unwrap();
+        let papers = [
+            ("Introduction", &[][..], "2020"),
+            ("Introduction", &[], "2021"),
+            ("Editorial", &[], ""),
+            ("Editorial", &[], ""),
+            ("Preface", &[], "2020"),
+            ("Preface", &["Nicole Megow"], "2020"),
+        ];
+        for (i, (title, authors, year)) in papers.into_iter().enumerate() {
+            // papers in the same venue would be one entry
+            let mut p = paper(title, authors, year);
+            p.metadata.venue = Venue::Journal(format!("Journal {}", i));
+            lib.add(p);
+        }
+
+        assert_eq!(
+            titles(&lib.duplicates()),
+            [["Preface 2020", "Preface 2020"]]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use tempfile::NamedTempFile;
+
+use super::{store::Store, LocalPaper};
+use crate::formats::native;
+
+/// Every paper in a TOML file `papers/<citekey>.toml` of its own, so that the library can be
+/// kept in a git repository with readable diffs and merges.
+#[derive(Debug)]
+pub struct FilesStore {
+    dir: PathBuf,
+    /// Content of every file when it was last read or written by us, by file name.
+    written: HashMap<String, String>,
+    stamps: HashMap<String, Stamp>,
+}
+
+/// Modification time and size of a file.
+type Stamp = (SystemTime, u64);
+
+impl FilesStore {
+    pub fn new(data_dir: &Path) -> Self {
+        FilesStore {
+            dir: data_dir.join("papers"),
+            written: HashMap::new(),
+            stamps: HashMap::new(),
+        }
+    }
+
+    /// Stamps of all paper files in the directory.
+    fn scan(&self) -> Result<HashMap<String, Stamp>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("could not read store: {}", self.dir.display()))
+            }
+        };
+        let mut stamps = HashMap::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".toml") && !name.starts_with('.') {
+                let meta = entry.metadata()?;
+                stamps.insert(name, (meta.modified()?, meta.len()));
+            }
+        }
+        Ok(stamps)
+    }
+
+    fn create_dir(&self) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("unable to create store directory: {}", self.dir.display()))
+    }
+}
+
+impl Store for FilesStore {
+    fn load(&mut self) -> Result<Vec<LocalPaper>> {
+        self.create_dir()?;
+        let stamps = self.scan()?;
+        let mut names: Vec<&String> = stamps.keys().collect();
+        names.sort();
+
+        self.written.clear();
+        let mut papers = Vec::with_capacity(names.len());
+        for name in names {
+            let path = self.dir.join(name);
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("could not read paper: {}", path.display()))?;
+            let mut paper = native::read_paper(&content)
+                .with_context(|| format!("could not load paper: {}", path.display()))?;
+            // the file name wins, so that renaming a file renames the key
+            paper.citekey = name.trim_end_matches(".toml").to_owned();
+            self.written.insert(name.clone(), content);
+            papers.push(paper);
+        }
+        self.stamps = stamps;
+        Ok(papers)
+    }
+
+    fn save(&mut self, papers: &[LocalPaper]) -> Result<()> {
+        self.create_dir()?;
+        let mut keep = HashSet::new();
+        for paper in papers {
+            let name = format!("{}.toml", paper.citekey);
+            let content = native::write_paper(paper)?;
+            let path = self.dir.join(&name);
+            // only touch files whose content actually changed
+            if self.written.get(&name) != Some(&content) || !path.exists() {
+                let mut file = NamedTempFile::new_in(&self.dir).with_context(|| {
+                    format!("could not create temporary file in: {}", self.dir.display())
+                })?;
+                file.write_all(content.as_bytes())
+                    .with_context(|| format!("could not write paper: {}", path.display()))?;
+                file.persist(&path)
+                    .with_context(|| format!("could not replace paper: {}", path.display()))?;
+                self.written.insert(name.clone(), content);
+            }
+            keep.insert(name);
+        }
+
+        for name in self.scan()?.into_keys() {
+            if !keep.contains(&name) {
+                let path = self.dir.join(&name);
+                fs::remove_file(&path)
+                    .with_context(|| format!("could not remove paper: {}", path.display()))?;
+                self.written.remove(&name);
+            }
+        }
+        self.stamps = self.scan()?;
+        Ok(())
+    }
+
+    fn changed(&self) -> Result<bool> {
+        Ok(self.scan()? != self.stamps)
+    }
+}
@@ -0,0 +1,68 @@
+//! Files which could not be filed into the library automatically, kept in `inbox.json` until
+//! they are accepted or dropped with `xivar inbox`.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use super::lock::StoreLock;
+use crate::resolve::Candidate;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboxEntry {
+    pub path: PathBuf,
+    /// Local time when the file was queued.
+    pub time: String,
+    /// Why the file was not filed automatically.
+    pub reason: String,
+    /// Papers found online for the file, best first.
+    pub candidates: Vec<Candidate>,
+}
+
+impl InboxEntry {
+    pub fn new<P: Into<PathBuf>>(path: P, reason: &str, candidates: Vec<Candidate>) -> Self {
+        InboxEntry {
+            path: path.into(),
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            reason: reason.to_owned(),
+            candidates,
+        }
+    }
+}
+
+fn inbox_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("inbox.json")
+}
+
+/// All queued files, oldest first.
+pub fn read(data_dir: &Path) -> Result<Vec<InboxEntry>> {
+    let path = inbox_path(data_dir);
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("could not load inbox: {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e).with_context(|| format!("could not read inbox: {}", path.display())),
+    }
+}
+
+/// Applies `f` to the queued files while holding the library lock, and writes the result.
+pub fn update<T>(data_dir: &Path, f: impl FnOnce(&mut Vec<InboxEntry>) -> T) -> Result<T> {
+    let _lock = StoreLock::acquire(data_dir)?;
+    let mut entries = read(data_dir)?;
+    let result = f(&mut entries);
+
+    let path = inbox_path(data_dir);
+    let mut file = NamedTempFile::new_in(data_dir)
+        .with_context(|| format!("could not create temporary file in: {}", data_dir.display()))?;
+    file.write_all(serde_json::to_string_pretty(&entries)?.as_bytes())
+        .with_context(|| format!("could not write inbox: {}", path.display()))?;
+    file.persist(&path)
+        .with_context(|| format!("could not replace inbox: {}", path.display()))?;
+    Ok(result)
+}
@@ -0,0 +1,66 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+
+use crate::query::QueryTerm;
+
+/// Inverted index from lowercase tokens to the positions of the papers containing them.
+#[derive(Debug, Default, Clone)]
+pub struct Index {
+    tokens: BTreeMap<String, BTreeSet<usize>>,
+}
+
+impl Index {
+    pub fn insert<I: IntoIterator<Item = String>>(&mut self, position: usize, tokens: I) {
+        for token in tokens {
+            self.tokens.entry(token).or_default().insert(position);
+        }
+    }
+
+    pub fn remove<I: IntoIterator<Item = String>>(&mut self, position: usize, tokens: I) {
+        for token in tokens {
+            if let Some(positions) = self.tokens.get_mut(&token) {
+                positions.remove(&position);
+                if positions.is_empty() {
+                    self.tokens.remove(&token);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tokens.clear();
+    }
+
+    /// Positions of all papers which have a token matching `term`, or `None` if `term` is not indexed.
+    pub fn lookup(&self, term: &QueryTerm) -> Option<BTreeSet<usize>> {
+        match term {
+            QueryTerm::Exact(q) => Some(self.tokens.get(q).cloned().unwrap_or_default()),
+            QueryTerm::Prefix(q) => Some(
+                self.tokens
+                    .range::<str, _>((Bound::Included(q.as_str()), Bound::Unbounded))
+                    .take_while(|(token, _)| token.starts_with(q.as_str()))
+                    .flat_map(|(_, positions)| positions.iter().copied())
+                    .collect(),
+            ),
+            QueryTerm::Filter(_) => None,
+        }
+    }
+
+    /// Positions of all papers matching every indexed term, or `None` if there are no such terms.
+    pub fn search<'a, I: IntoIterator<Item = &'a QueryTerm>>(
+        &self,
+        terms: I,
+    ) -> Option<BTreeSet<usize>> {
+        let mut result: Option<BTreeSet<usize>> = None;
+        for positions in terms.into_iter().filter_map(|term| self.lookup(term)) {
+            result = Some(match result {
+                None => positions,
+                Some(result) => result.intersection(&positions).copied().collect(),
+            });
+            if matches!(&result, Some(r) if r.is_empty()) {
+                break;
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,294 @@
+//! Append-only log `journal.jsonl` of all changes to the library, one JSON operation per line.
+//!
+//! Every operation stores the affected papers before and after the change, so that it can be
+//! reverted. Reverting an operation appends a new `revert` operation instead of rewriting the log.
+//! Only when the log grows beyond `MAX_SIZE`, the oldest operations are dropped.
+
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use super::LocalPaper;
+
+/// Size of the journal in bytes beyond which the oldest operations are dropped, until it is half
+/// as large.
+const MAX_SIZE: u64 = 16 << 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: u64,
+    /// Local time of the save which recorded the operation.
+    pub time: String,
+    pub kind: OperationKind,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Add,
+    Edit,
+    Relocate,
+    /// Moves files of papers, which are moved back when the operation is undone.
+    Organize,
+    Remove,
+    Merge,
+    Restore,
+    /// Undoes the operation with the given id.
+    Revert(u64),
+}
+
+impl Display for OperationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationKind::Add => write!(f, "add"),
+            OperationKind::Edit => write!(f, "edit"),
+            OperationKind::Relocate => write!(f, "relocate"),
+            OperationKind::Organize => write!(f, "organize"),
+            OperationKind::Remove => write!(f, "remove"),
+            OperationKind::Merge => write!(f, "merge"),
+            OperationKind::Restore => write!(f, "restore"),
+            OperationKind::Revert(id) => write!(f, "undo {}", id),
+        }
+    }
+}
+
+/// A paper before and after an operation, `None` if it did not exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub before: Option<LocalPaper>,
+    pub after: Option<LocalPaper>,
+}
+
+impl Operation {
+    /// Short description of the affected papers.
+    pub fn summary(&self) -> String {
+        let titles: Vec<String> = self
+            .changes
+            .iter()
+            .filter_map(|c| c.after.as_ref().or(c.before.as_ref()))
+            .map(|p| p.metadata().title.to_string())
+            .unique()
+            .collect();
+        match titles.as_slice() {
+            [] => String::new(),
+            [title] => title.clone(),
+            [title, rest @ ..] => format!("{} and {} more", title, rest.len()),
+        }
+    }
+}
+
+fn journal_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("journal.jsonl")
+}
+
+/// All operations in the order they were recorded. Lines which cannot be read, e.g. written by a
+/// newer version of xivar, are skipped.
+pub fn read(data_dir: &Path) -> Result<Vec<Operation>> {
+    let path = journal_path(data_dir);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => {
+            return Err(e).with_context(|| format!("could not read journal: {}", path.display()))
+        }
+    };
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(op) => Some(op),
+            Err(err) => {
+                log::warn!("Skipping unreadable journal entry: {}", err);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Appends the operations, numbered after the last recorded one.
+pub fn append(data_dir: &Path, ops: Vec<(OperationKind, Vec<Change>)>) -> Result<()> {
+    if ops.is_empty() {
+        return Ok(());
+    }
+    let path = journal_path(data_dir);
+    let first =
+        last_id(&path).with_context(|| format!("could not read journal: {}", path.display()))? + 1;
+    let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let mut lines = String::new();
+    for (id, (kind, changes)) in (first..).zip(ops) {
+        let op = Operation {
+            id,
+            time: time.clone(),
+            kind,
+            changes,
+        };
+        lines.push_str(&serde_json::to_string(&op)?);
+        lines.push('\n');
+    }
+
+    let size = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| {
+            file.write_all(lines.as_bytes())?;
+            file.metadata()
+        })
+        .with_context(|| format!("could not write to journal: {}", path.display()))?
+        .len();
+    if size > MAX_SIZE {
+        compact(data_dir, MAX_SIZE / 2)?;
+    }
+    Ok(())
+}
+
+/// The id of the last operation, which is read from the end of the journal, as the whole journal
+/// can be large. 0 if there is none.
+fn last_id(path: &Path) -> Result<u64> {
+    #[derive(Deserialize)]
+    struct Id {
+        id: u64,
+    }
+
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let len = file.metadata()?.len();
+    let mut tail_len = 4096;
+    loop {
+        let start = len.saturating_sub(tail_len);
+        let mut tail = vec![];
+        file.seek(SeekFrom::Start(start))?;
+        (&mut file).take(len - start).read_to_end(&mut tail)?;
+        let tail = String::from_utf8_lossy(&tail);
+        // the first line of the tail may be cut off
+        let lines: Vec<&str> = tail.lines().skip(usize::from(start > 0)).collect();
+        if let Some(id) = lines
+            .iter()
+            .rev()
+            .find_map(|line| serde_json::from_str::<Id>(line).ok())
+        {
+            return Ok(id.id);
+        }
+        if start == 0 {
+            return Ok(0);
+        }
+        tail_len *= 4;
+    }
+}
+
+/// Drops the oldest operations until the journal is at most `size` bytes, but keeps the last
+/// one, so that ids keep increasing.
+fn compact(data_dir: &Path, size: u64) -> Result<()> {
+    let path = journal_path(data_dir);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("could not read journal: {}", path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let mut kept_size = 0;
+    let kept = lines
+        .iter()
+        .rev()
+        .take_while(|line| {
+            kept_size += line.len() as u64 + 1;
+            kept_size <= size
+        })
+        .count()
+        .max(1);
+    log::warn!(
+        "Dropping the oldest {} operations of the journal",
+        lines.len() - kept
+    );
+
+    let mut file = NamedTempFile::new_in(data_dir)
+        .with_context(|| format!("could not create temporary file in: {}", data_dir.display()))?;
+    for line in &lines[lines.len() - kept..] {
+        writeln!(file, "{}", line)
+            .with_context(|| format!("could not write journal: {}", path.display()))?;
+    }
+    file.persist(&path)
+        .with_context(|| format!("could not replace journal: {}", path.display()))?;
+    Ok(())
+}
+
+/// The last `n` operations which can still be undone, newest first.
+pub fn undoable(ops: &[Operation], n: usize) -> Vec<&Operation> {
+    let reverted: HashSet<u64> = ops
+        .iter()
+        .filter_map(|op| match op.kind {
+            OperationKind::Revert(id) => Some(id),
+            _ => None,
+        })
+        .collect();
+    ops.iter()
+        .rev()
+        .filter(|op| !matches!(op.kind, OperationKind::Revert(_)) && !reverted.contains(&op.id))
+        .take(n)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(data_dir: &Path) -> Vec<u64> {
+        read(data_dir).unwrap().iter().map(|op| op.id).collect()
+    }
+
+    #[test]
+    fn ids_continue_after_the_last_operation() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(last_id(&journal_path(dir.path())).unwrap(), 0);
+        append(dir.path(), vec![(OperationKind::Add, vec![])]).unwrap();
+        // operations longer than the tail which is read at first
+        let padding = vec![
+            Change {
+                before: None,
+                after: None,
+            };
+            1000
+        ];
+        append(
+            dir.path(),
+            vec![
+                (OperationKind::Edit, padding.clone()),
+                (OperationKind::Edit, padding),
+            ],
+        )
+        .unwrap();
+        append(dir.path(), vec![(OperationKind::Revert(3), vec![])]).unwrap();
+        assert_eq!(ids(dir.path()), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn compact_keeps_the_newest_operations() {
+        let dir = tempfile::tempdir().unwrap();
+        for _ in 0..10 {
+            append(dir.path(), vec![(OperationKind::Add, vec![])]).unwrap();
+        }
+        let line = fs::read_to_string(journal_path(dir.path()))
+            .unwrap()
+            .lines()
+            .last()
+            .unwrap()
+            .len() as u64;
+        compact(dir.path(), 3 * line + 3).unwrap();
+        assert_eq!(ids(dir.path()), [8, 9, 10]);
+        compact(dir.path(), 0).unwrap();
+        assert_eq!(ids(dir.path()), [10]);
+        append(dir.path(), vec![(OperationKind::Add, vec![])]).unwrap();
+        assert_eq!(ids(dir.path()), [10, 11]);
+    }
+}
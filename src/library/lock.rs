@@ -0,0 +1,39 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use fs2::FileExt;
+
+/// Advisory lock on `lib.lock` in the data directory, serializing access of
+/// concurrent xivar processes to the store. Released on drop.
+#[derive(Debug)]
+pub struct StoreLock(File);
+
+impl StoreLock {
+    pub fn acquire(data_dir: &Path) -> Result<StoreLock> {
+        fs::create_dir_all(data_dir).with_context(|| {
+            format!("unable to create data directory: {}", data_dir.display())
+        })?;
+        let path = data_dir.join("lib.lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("could not open lock file: {}", path.display()))?;
+        if file.try_lock_exclusive().is_err() {
+            log::info!("Waiting for another xivar process to release the library...");
+            file.lock_exclusive()
+                .with_context(|| format!("could not lock: {}", path.display()))?;
+        }
+        Ok(StoreLock(file))
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.0);
+    }
+}
@@ -0,0 +1,661 @@
+//! Upgrades of stores written by older versions of xivar.
+//!
+//! Every layout that has ever been written to disk is kept below as a frozen
+//! copy in its own module `vN`. These types must never be changed. When the
+//! layout of `LocalPaper` changes, copy the current layout into a new module,
+//! bump `Library::CURRENT_VERSION`, and add an upgrade step from the previous
+//! frozen layout to both `load` and `load_paper`. The frozen types do not
+//! refer to any type of the crate, and `fixtures` holds a store written with
+//! every layout, which the tests below load.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use bincode::Options;
+use serde::de::DeserializeOwned;
+
+use super::{Attachment, Library, LibraryVersion, LocalPaper, Rating};
+
+/// Deserializes the papers of a store written with `version` and upgrades them to the current layout.
+pub fn load(version: LibraryVersion, buffer: &[u8]) -> Result<Vec<LocalPaper>> {
+    match version.0 {
+        v if v == Library::CURRENT_VERSION.0 => deserialize(buffer),
+        1 => Ok(from_v1(deserialize(buffer)?)),
+        2 => Ok(from_v2(deserialize(buffer)?)),
+        3 => Ok(from_v3(deserialize(buffer)?)),
+        4 => Ok(from_v4(deserialize(buffer)?)),
+        5 => Ok(from_v5(deserialize(buffer)?)),
+        6 => Ok(from_v6(deserialize(buffer)?)),
+        v => bail!("unknown store version {}", v),
+    }
+}
+
+/// Deserializes a single paper written with `version` and upgrades it to the current layout.
+#[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+pub fn load_paper(version: LibraryVersion, buffer: &[u8]) -> Result<LocalPaper> {
+    let mut papers = match version.0 {
+        v if v == Library::CURRENT_VERSION.0 => return deserialize(buffer),
+        1 => from_v1(vec![deserialize(buffer)?]),
+        2 => from_v2(vec![deserialize(buffer)?]),
+        3 => from_v3(vec![deserialize(buffer)?]),
+        4 => from_v4(vec![deserialize(buffer)?]),
+        5 => from_v5(vec![deserialize(buffer)?]),
+        6 => from_v6(vec![deserialize(buffer)?]),
+        v => bail!("unknown store version {}", v),
+    };
+    Ok(papers.remove(0))
+}
+
+/// Copies `path` next to itself before it gets overwritten with a newer layout.
+pub fn backup(path: &Path, version: LibraryVersion) -> Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(
+        ".v{}-{}.bak",
+        version.0,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let backup = path.with_file_name(name);
+    fs::copy(path, &backup)
+        .with_context(|| format!("could not back up store to: {}", backup.display()))?;
+    Ok(backup)
+}
+
+fn deserialize<T: DeserializeOwned>(buffer: &[u8]) -> Result<T> {
+    Ok(bincode::options()
+        .with_fixint_encoding()
+        .deserialize(buffer)?)
+}
+
+fn from_v1(papers: Vec<v1::LocalPaper>) -> Vec<LocalPaper> {
+    from_v2(
+        papers
+            .into_iter()
+            .map(|p| v2::LocalPaper {
+                metadata: p.metadata,
+                location: p.location,
+                ees: p.ees,
+                tags: vec![],
+                collections: vec![],
+            })
+            .collect(),
+    )
+}
+
+fn from_v2(papers: Vec<v2::LocalPaper>) -> Vec<LocalPaper> {
+    from_v3(
+        papers
+            .into_iter()
+            .map(|p| v3::LocalPaper {
+                metadata: p.metadata,
+                location: p.location,
+                ees: p.ees,
+                tags: p.tags,
+                collections: p.collections,
+                note: String::new(),
+            })
+            .collect(),
+    )
+}
+
+fn from_v3(papers: Vec<v3::LocalPaper>) -> Vec<LocalPaper> {
+    from_v4(
+        papers
+            .into_iter()
+            .map(|p| v4::LocalPaper {
+                metadata: p.metadata,
+                location: p.location,
+                ees: p.ees,
+                tags: p.tags,
+                collections: p.collections,
+                note: p.note,
+                status: v4::ReadingStatus::Unread,
+                priority: v4::Priority::Normal,
+                rating: None,
+            })
+            .collect(),
+    )
+}
+
+fn from_v4(papers: Vec<v4::LocalPaper>) -> Vec<LocalPaper> {
+    from_v5(
+        papers
+            .into_iter()
+            .map(|p| {
+                // Papers downloaded from arXiv were the only ones with an arXiv PDF link.
+                let role = if p.ees.iter().any(|ee| ee.0.contains("arxiv.org/pdf")) {
+                    v5::AttachmentRole::Arxiv(None)
+                } else {
+                    v5::AttachmentRole::Main
+                };
+                v5::LocalPaper {
+                    metadata: p.metadata,
+                    attachments: vec![v5::Attachment {
+                        path: p.location,
+                        role,
+                    }],
+                    ees: p.ees,
+                    tags: p.tags,
+                    collections: p.collections,
+                    note: p.note,
+                    status: p.status,
+                    priority: p.priority,
+                    rating: p.rating,
+                }
+            })
+            .collect(),
+    )
+}
+
+fn from_v5(papers: Vec<v5::LocalPaper>) -> Vec<LocalPaper> {
+    from_v6(
+        papers
+            .into_iter()
+            .map(|p| v6::LocalPaper {
+                metadata: p.metadata,
+                attachments: p
+                    .attachments
+                    .into_iter()
+                    .map(|a| v6::Attachment {
+                        path: a.path,
+                        role: a.role,
+                        hash: None,
+                    })
+                    .collect(),
+                ees: p.ees,
+                tags: p.tags,
+                collections: p.collections,
+                note: p.note,
+                status: p.status,
+                priority: p.priority,
+                rating: p.rating,
+            })
+            .collect(),
+    )
+}
+
+/// Citation keys are generated by `LocalPaper::new`, and made unique when the library is opened.
+fn from_v6(papers: Vec<v6::LocalPaper>) -> Vec<LocalPaper> {
+    papers
+        .into_iter()
+        .map(|p| {
+            let mut paper = LocalPaper::new(
+                p.metadata.into(),
+                p.attachments.into_iter().map(Attachment::from).collect(),
+                p.ees.into_iter().map(Into::into).collect(),
+            );
+            paper.tags = p.tags;
+            paper.collections = p.collections;
+            paper.note = p.note;
+            paper.status = p.status.into();
+            paper.priority = p.priority.into();
+            paper.rating = p.rating.and_then(Rating::new);
+            paper
+        })
+        .collect()
+}
+
+/// Layout written by xivar 0.5.
+mod v1 {
+    use std::path::PathBuf;
+
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct LocalPaper {
+        pub metadata: PaperInfo,
+        pub location: PathBuf,
+        pub ees: Vec<PaperUrl>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct PaperInfo {
+        pub id: Option<Identifier>,
+        pub title: PaperTitle,
+        pub venue: Venue,
+        pub authors: Vec<String>,
+        pub year: String,
+        pub summary: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    pub enum Identifier {
+        Arxiv(ArxivIdentifier),
+        Doi(Doi),
+        Custom(String),
+    }
+
+    #[derive(Deserialize)]
+    pub struct ArxivIdentifier {
+        pub year: u32,
+        pub month: u32,
+        pub number: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Doi {
+        pub organization: u32,
+        pub id: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct PaperTitle {
+        pub words: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    pub enum Venue {
+        Journal(String),
+        Conf(String),
+        Arxiv(String),
+    }
+
+    #[derive(Deserialize)]
+    pub struct PaperUrl(pub String);
+
+    impl From<PaperInfo> for crate::PaperInfo {
+        fn from(info: PaperInfo) -> Self {
+            crate::PaperInfo {
+                id: info.id.map(Into::into),
+                title: crate::PaperTitle {
+                    words: info.title.words,
+                },
+                venue: info.venue.into(),
+                authors: info.authors,
+                year: info.year,
+                summary: info.summary,
+            }
+        }
+    }
+
+    /// Goes through the textual form, as the fields of the current identifiers are private.
+    impl From<Identifier> for crate::Identifier {
+        fn from(id: Identifier) -> Self {
+            match id {
+                Identifier::Arxiv(arxiv) => {
+                    let id = format!("{:0>2}{:0>2}.{}", arxiv.year, arxiv.month, arxiv.number);
+                    match crate::ArxivIdentifier::parse_string(&id) {
+                        Ok(arxiv) => crate::Identifier::Arxiv(arxiv),
+                        Err(_) => crate::Identifier::Custom(id),
+                    }
+                }
+                Identifier::Doi(doi) => {
+                    let id = format!("10.{}/{}", doi.organization, doi.id);
+                    match crate::Doi::parse_doi(&id) {
+                        Ok(doi) => crate::Identifier::Doi(doi),
+                        Err(_) => crate::Identifier::Custom(id),
+                    }
+                }
+                Identifier::Custom(custom) => crate::Identifier::Custom(custom),
+            }
+        }
+    }
+
+    impl From<Venue> for crate::Venue {
+        fn from(venue: Venue) -> Self {
+            match venue {
+                Venue::Journal(name) => crate::Venue::Journal(name),
+                Venue::Conf(name) => crate::Venue::Conf(name),
+                Venue::Arxiv(name) => crate::Venue::Arxiv(name),
+            }
+        }
+    }
+
+    impl From<PaperUrl> for crate::PaperUrl {
+        fn from(url: PaperUrl) -> Self {
+            crate::PaperUrl::new(url.0)
+        }
+    }
+}
+
+/// Adds tags and collections.
+mod v2 {
+    use std::path::PathBuf;
+
+    use serde::Deserialize;
+
+    use super::v1::{PaperInfo, PaperUrl};
+
+    #[derive(Deserialize)]
+    pub struct LocalPaper {
+        pub metadata: PaperInfo,
+        pub location: PathBuf,
+        pub ees: Vec<PaperUrl>,
+        pub tags: Vec<String>,
+        pub collections: Vec<String>,
+    }
+}
+
+/// Adds notes.
+mod v3 {
+    use std::path::PathBuf;
+
+    use serde::Deserialize;
+
+    use super::v1::{PaperInfo, PaperUrl};
+
+    #[derive(Deserialize)]
+    pub struct LocalPaper {
+        pub metadata: PaperInfo,
+        pub location: PathBuf,
+        pub ees: Vec<PaperUrl>,
+        pub tags: Vec<String>,
+        pub collections: Vec<String>,
+        pub note: String,
+    }
+}
+
+/// Adds reading status, priority and rating.
+mod v4 {
+    use std::path::PathBuf;
+
+    use serde::Deserialize;
+
+    use super::v1::{PaperInfo, PaperUrl};
+
+    #[derive(Deserialize)]
+    pub struct LocalPaper {
+        pub metadata: PaperInfo,
+        pub location: PathBuf,
+        pub ees: Vec<PaperUrl>,
+        pub tags: Vec<String>,
+        pub collections: Vec<String>,
+        pub note: String,
+        pub status: ReadingStatus,
+        pub priority: Priority,
+        pub rating: Option<u8>,
+    }
+
+    #[derive(Deserialize)]
+    pub enum ReadingStatus {
+        Unread,
+        Reading,
+        Read,
+    }
+
+    #[derive(Deserialize)]
+    pub enum Priority {
+        Low,
+        Normal,
+        High,
+    }
+
+    impl From<ReadingStatus> for crate::library::ReadingStatus {
+        fn from(status: ReadingStatus) -> Self {
+            match status {
+                ReadingStatus::Unread => crate::library::ReadingStatus::Unread,
+                ReadingStatus::Reading => crate::library::ReadingStatus::Reading,
+                ReadingStatus::Read => crate::library::ReadingStatus::Read,
+            }
+        }
+    }
+
+    impl From<Priority> for crate::library::Priority {
+        fn from(priority: Priority) -> Self {
+            match priority {
+                Priority::Low => crate::library::Priority::Low,
+                Priority::Normal => crate::library::Priority::Normal,
+                Priority::High => crate::library::Priority::High,
+            }
+        }
+    }
+}
+
+/// Replaces the location by a list of attachments.
+mod v5 {
+    use std::path::PathBuf;
+
+    use serde::Deserialize;
+
+    use super::v1::{PaperInfo, PaperUrl};
+    use super::v4::{Priority, ReadingStatus};
+
+    #[derive(Deserialize)]
+    pub struct LocalPaper {
+        pub metadata: PaperInfo,
+        pub attachments: Vec<Attachment>,
+        pub ees: Vec<PaperUrl>,
+        pub tags: Vec<String>,
+        pub collections: Vec<String>,
+        pub note: String,
+        pub status: ReadingStatus,
+        pub priority: Priority,
+        pub rating: Option<u8>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Attachment {
+        pub path: PathBuf,
+        pub role: AttachmentRole,
+    }
+
+    #[derive(Deserialize)]
+    pub enum AttachmentRole {
+        Main,
+        Supplement,
+        Slides,
+        Arxiv(Option<u32>),
+    }
+
+    impl From<AttachmentRole> for crate::library::AttachmentRole {
+        fn from(role: AttachmentRole) -> Self {
+            use crate::library::AttachmentRole as Role;
+            match role {
+                AttachmentRole::Main => Role::Main,
+                AttachmentRole::Supplement => Role::Supplement,
+                AttachmentRole::Slides => Role::Slides,
+                AttachmentRole::Arxiv(version) => Role::Arxiv(version),
+            }
+        }
+    }
+}
+
+/// Adds content hashes of attachments.
+mod v6 {
+    use std::path::PathBuf;
+
+    use serde::Deserialize;
+
+    use super::v1::{PaperInfo, PaperUrl};
+    use super::v4::{Priority, ReadingStatus};
+    use super::v5::AttachmentRole;
+
+    #[derive(Deserialize)]
+    pub struct LocalPaper {
+        pub metadata: PaperInfo,
+        pub attachments: Vec<Attachment>,
+        pub ees: Vec<PaperUrl>,
+        pub tags: Vec<String>,
+        pub collections: Vec<String>,
+        pub note: String,
+        pub status: ReadingStatus,
+        pub priority: Priority,
+        pub rating: Option<u8>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Attachment {
+        pub path: PathBuf,
+        pub role: AttachmentRole,
+        pub hash: Option<ContentHash>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ContentHash {
+        pub size: u64,
+        pub sha1: [u8; 20],
+    }
+
+    impl From<Attachment> for crate::library::Attachment {
+        fn from(attachment: Attachment) -> Self {
+            let mut new = crate::library::Attachment::new(attachment.path, attachment.role.into());
+            new.hash = attachment.hash.map(|h| crate::library::ContentHash {
+                size: h.size,
+                sha1: h.sha1,
+            });
+            new
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        library::{bincode_store, AttachmentRole, Priority, ReadingStatus},
+        Identifier, Venue,
+    };
+
+    /// Stores written by every layout, each with an arXiv preprint and a conference paper, and
+    /// the preprint on its own as the SQLite store writes a row. The last one is the current
+    /// layout.
+    const FIXTURES: [(u32, &[u8], &[u8]); 7] = [
+        (
+            1,
+            include_bytes!("fixtures/v1.db"),
+            include_bytes!("fixtures/v1.paper"),
+        ),
+        (
+            2,
+            include_bytes!("fixtures/v2.db"),
+            include_bytes!("fixtures/v2.paper"),
+        ),
+        (
+            3,
+            include_bytes!("fixtures/v3.db"),
+            include_bytes!("fixtures/v3.paper"),
+        ),
+        (
+            4,
+            include_bytes!("fixtures/v4.db"),
+            include_bytes!("fixtures/v4.paper"),
+        ),
+        (
+            5,
+            include_bytes!("fixtures/v5.db"),
+            include_bytes!("fixtures/v5.paper"),
+        ),
+        (
+            6,
+            include_bytes!("fixtures/v6.db"),
+            include_bytes!("fixtures/v6.paper"),
+        ),
+        (
+            7,
+            include_bytes!("fixtures/v7.db"),
+            include_bytes!("fixtures/v7.paper"),
+        ),
+    ];
+
+    fn check_preprint(version: u32, paper: &LocalPaper) {
+        let info = paper.metadata();
+        assert_eq!(
+            info.id.as_ref().map(Identifier::to_prefixed_string),
+            Some("arxiv:2101.01234".to_owned())
+        );
+        assert_eq!(
+            info.title.to_string(),
+            "Learning-Augmented Online Algorithms"
+        );
+        assert_eq!(info.venue, Venue::Arxiv("CoRR".to_owned()));
+        assert_eq!(info.authors, ["Alexander Lindermayr", "Nicole Megow"]);
+        assert_eq!(info.year, "2021");
+        assert!(info.summary.is_some());
+        assert_eq!(paper.ees[0].raw(), "https://arxiv.org/pdf/2101.01234v2");
+        // keys are saved since v7, before they were generated
+        let citekey = if version >= 7 {
+            "lindermayr2021online"
+        } else {
+            "lindermayr2021learning"
+        };
+        assert_eq!(paper.citekey, citekey);
+
+        let main = &paper.attachments[0];
+        assert_eq!(
+            main.path.to_str(),
+            Some("/papers/lindermayr2021learning.pdf")
+        );
+        if version < 5 {
+            assert_eq!(paper.attachments.len(), 1);
+            assert_eq!(main.role, AttachmentRole::Arxiv(None));
+        } else {
+            assert_eq!(paper.attachments.len(), 2);
+            assert_eq!(main.role, AttachmentRole::Arxiv(Some(2)));
+            assert_eq!(paper.attachments[1].role, AttachmentRole::Slides);
+        }
+        assert_eq!(main.hash.map(|h| h.size), (version >= 6).then_some(1234));
+
+        if version >= 2 {
+            assert_eq!(paper.tags, ["ml", "online"]);
+            assert_eq!(paper.collections, ["thesis"]);
+        } else {
+            assert!(paper.tags.is_empty() && paper.collections.is_empty());
+        }
+        let note = if version >= 3 { "Read section 3." } else { "" };
+        assert_eq!(paper.note, note);
+        if version >= 4 {
+            assert_eq!(paper.status, ReadingStatus::Reading);
+            assert_eq!(paper.priority, Priority::High);
+            assert_eq!(paper.rating.map(|r| r.stars()), Some(4));
+        } else {
+            assert_eq!(paper.status, ReadingStatus::Unread);
+            assert_eq!(paper.priority, Priority::Normal);
+            assert_eq!(paper.rating, None);
+        }
+    }
+
+    fn check_conference(version: u32, paper: &LocalPaper) {
+        let info = paper.metadata();
+        assert_eq!(
+            info.id.as_ref().map(Identifier::to_prefixed_string),
+            Some("doi:10.1145/3519935.3520010".to_owned())
+        );
+        assert_eq!(info.venue, Venue::Conf("STOC".to_owned()));
+        assert_eq!(info.summary, None);
+        assert_eq!(paper.attachments.len(), 1);
+        assert_eq!(paper.attachments[0].role, AttachmentRole::Main);
+        if version >= 4 {
+            assert_eq!(paper.status, ReadingStatus::Read);
+            assert_eq!(paper.priority, Priority::Low);
+        }
+    }
+
+    #[test]
+    fn load_fixtures() {
+        for (version, store, _) in FIXTURES {
+            let (stored, papers) = store.split_at(4);
+            assert_eq!(deserialize::<LibraryVersion>(stored).unwrap().0, version);
+            let papers = load(LibraryVersion(version), papers).unwrap();
+            assert_eq!(papers.len(), 2, "v{}", version);
+            check_preprint(version, &papers[0]);
+            check_conference(version, &papers[1]);
+        }
+    }
+
+    #[test]
+    fn load_paper_fixtures() {
+        for (version, _, paper) in FIXTURES {
+            let paper = load_paper(LibraryVersion(version), paper).unwrap();
+            check_preprint(version, &paper);
+        }
+    }
+
+    #[test]
+    fn current_layout_is_unchanged() {
+        // a change of the layout needs a new version, a migration and a fixture
+        let (version, store, paper) = FIXTURES[FIXTURES.len() - 1];
+        assert_eq!(version, Library::CURRENT_VERSION.0);
+        let papers = load(LibraryVersion(version), &store[4..]).unwrap();
+        assert_eq!(bincode_store::encode(&papers).unwrap(), store);
+        assert_eq!(bincode::serialize(&papers[0]).unwrap(), paper);
+    }
+
+    #[test]
+    fn reject_unknown_version() {
+        let (_, store, paper) = FIXTURES[0];
+        assert!(load(LibraryVersion(0), &store[4..]).is_err());
+        assert!(load_paper(LibraryVersion(Library::CURRENT_VERSION.0 + 1), paper).is_err());
+    }
+}
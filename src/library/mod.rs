@@ -0,0 +1,797 @@
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+pub use crate::Query;
+use crate::{query::Filter, template::Template, xiv_config::Config, PaperInfo, PaperUrl};
+use anyhow::Result;
+
+mod attachment;
+pub mod backup;
+pub mod bincode_store;
+pub mod citekey;
+mod dedup;
+mod files_store;
+pub mod inbox;
+mod index;
+pub mod journal;
+mod lock;
+mod migrations;
+mod reading;
+mod relocate;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+pub mod store;
+
+pub use attachment::{Attachment, AttachmentRole, ContentHash};
+use index::Index;
+use journal::{Change, Operation, OperationKind};
+use lock::StoreLock;
+pub use reading::{Priority, Rating, ReadingStatus};
+pub use store::Backend;
+use store::{open_store, Store};
+
+#[derive(Debug)]
+pub enum LibReq {
+    Save {
+        paper: LocalPaper,
+    },
+    Update {
+        paper: LocalPaper,
+    },
+    Query {
+        res_channel: tokio::sync::oneshot::Sender<Vec<LocalPaper>>,
+        query: Query,
+        max_hits: usize,
+    },
+    /// Undo the last change, answering with a description of it
+    Undo {
+        res_channel: tokio::sync::oneshot::Sender<Result<String>>,
+    },
+}
+
+#[derive(Debug)]
+pub enum LoadingResult {
+    Success,
+    Failure(String),
+}
+
+pub async fn lib_manager_fut(
+    config: Config,
+    mut req_recv: tokio::sync::mpsc::Receiver<LibReq>,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    loading_tx: tokio::sync::mpsc::Sender<LoadingResult>,
+) {
+    log::info!("Load library...");
+
+    match Library::open(&config) {
+        Ok(mut lib) => {
+            log::info!("Library loaded! {} local entries.", lib.size());
+            loading_tx.send(LoadingResult::Success).await.unwrap();
+            loop {
+                tokio::select! {
+                    req = req_recv.recv() => {
+                        if let Some(req) = req {
+                                match req {
+                                    LibReq::Save { paper } => {
+                                        lib.add(paper);
+                                        // save right away, so that `xivar watch` sees the download
+                                        // as part of the library
+                                        if let Err(err) = lib.save() {
+                                            log::error!("Could not save library: {:#}", err);
+                                        }
+                                    }
+                                    LibReq::Update { paper } => {
+                                        lib.update(paper);
+                                    }
+                                    LibReq::Query { res_channel, query, max_hits } => {
+                                        let results = lib.iter_matches(&query).take(max_hits).cloned().collect();
+                                        res_channel.send(results).unwrap();
+                                    }
+                                    LibReq::Undo { res_channel } => {
+                                        let result = lib.undo(1).map(|undone| match undone.first() {
+                                            Some((op, _)) => format!("Undid {} {}", op.kind, op.summary()),
+                                            None => "Nothing to undo.".to_owned(),
+                                        });
+                                        res_channel.send(result).ok();
+                                    }
+                                }
+                        }
+                    }
+                    _ = shutdown_rx.recv() => break
+                }
+            }
+        }
+        Err(err) => {
+            loading_tx
+                .send(LoadingResult::Failure(err.to_string()))
+                .await
+                .unwrap();
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct LocalPaper {
+    pub metadata: PaperInfo,
+    /// Citation key, unique within the library.
+    #[serde(default)]
+    pub citekey: String,
+    pub attachments: Vec<Attachment>,
+    pub ees: Vec<PaperUrl>,
+    pub tags: Vec<String>,
+    pub collections: Vec<String>,
+    /// Free-form Markdown notes, empty if there are none.
+    pub note: String,
+    pub status: ReadingStatus,
+    pub priority: Priority,
+    pub rating: Option<Rating>,
+}
+
+impl LocalPaper {
+    pub fn new(metadata: PaperInfo, attachments: Vec<Attachment>, ees: Vec<PaperUrl>) -> Self {
+        LocalPaper {
+            citekey: citekey::generate(&metadata),
+            metadata,
+            attachments,
+            ees,
+            tags: vec![],
+            collections: vec![],
+            note: String::new(),
+            status: ReadingStatus::default(),
+            priority: Priority::default(),
+            rating: None,
+        }
+    }
+
+    /// Lowercase words of the metadata and the note, as used for searching.
+    pub fn tokens(&self) -> impl Iterator<Item = String> + '_ {
+        self.metadata.tokens().chain(
+            self.note
+                .split_whitespace()
+                .map(|t| {
+                    t.trim_matches(|c: char| !c.is_alphanumeric())
+                        .to_lowercase()
+                })
+                .filter(|t| !t.is_empty()),
+        )
+    }
+
+    /// Whether the paper matches the text terms and all filters of `query`.
+    pub fn matches(&self, query: &Query) -> bool {
+        self.matches_filters(query) && query.matches_tokens(&self.tokens().collect::<Vec<String>>())
+    }
+
+    fn matches_filters(&self, query: &Query) -> bool {
+        query.filters().all(|filter| match filter {
+            Filter::Tag(tag) => contains_ignore_case(&self.tags, tag),
+            Filter::Collection(name) => contains_ignore_case(&self.collections, name),
+            Filter::Status(status) => ReadingStatus::parse(status) == Some(self.status),
+            Filter::Priority(priority) => Priority::parse(priority) == Some(self.priority),
+            Filter::Rating(stars) => match stars.parse::<u8>() {
+                Ok(stars) => matches!(self.rating, Some(r) if r.stars() >= stars),
+                Err(_) => false,
+            },
+        })
+    }
+
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        add_ignore_case(&mut self.tags, tag)
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        remove_ignore_case(&mut self.tags, tag)
+    }
+
+    pub fn add_to_collection(&mut self, name: &str) -> bool {
+        add_ignore_case(&mut self.collections, name)
+    }
+
+    pub fn remove_from_collection(&mut self, name: &str) -> bool {
+        remove_ignore_case(&mut self.collections, name)
+    }
+
+    /// Gives one more star, up to the maximum rating.
+    pub fn rate_up(&mut self) {
+        let stars = self.rating.map_or(0, |r| r.stars());
+        if let Some(rating) = Rating::new(stars + 1) {
+            self.rating = Some(rating);
+        }
+    }
+
+    /// Takes away one star, removing the rating below one star.
+    pub fn rate_down(&mut self) {
+        self.rating = self.rating.and_then(|r| Rating::new(r.stars() - 1));
+    }
+
+    /// The file to open by default: the main version if there is one, otherwise the latest preprint.
+    pub fn location(&self) -> Option<&Path> {
+        self.attachments
+            .iter()
+            .max_by_key(|a| match a.role {
+                AttachmentRole::Main => (3, 0),
+                AttachmentRole::Arxiv(version) => (2, version.unwrap_or(0)),
+                AttachmentRole::Supplement => (1, 0),
+                AttachmentRole::Slides => (0, 0),
+            })
+            .map(|a| a.path.as_path())
+    }
+
+    /// Adds `attachment`, or replaces the attachment with the same path, whose file may have been
+    /// overwritten. Returns `false` if the paper already has an attachment with this path and role.
+    pub fn attach(&mut self, attachment: Attachment) -> bool {
+        match self
+            .attachments
+            .iter_mut()
+            .find(|a| a.path == attachment.path)
+        {
+            Some(a) => {
+                let changed = a.role != attachment.role;
+                *a = attachment;
+                changed
+            }
+            None => {
+                self.attachments.push(attachment);
+                true
+            }
+        }
+    }
+
+    /// Hashes all existing files which have not been hashed yet. Returns the number of new hashes.
+    pub fn update_hashes(&mut self) -> usize {
+        let mut hashed = 0;
+        for attachment in &mut self.attachments {
+            match attachment.update_hash() {
+                Ok(true) => hashed += 1,
+                Ok(false) => {}
+                Err(err) => log::warn!("Could not hash {:?}: {}", attachment.path, err),
+            }
+        }
+        hashed
+    }
+
+    pub fn detach(&mut self, path: &Path) -> bool {
+        let len = self.attachments.len();
+        self.attachments.retain(|a| a.path != path);
+        self.attachments.len() != len
+    }
+
+    /// Adds the files, links, tags and collections of `other` to this paper. Notes, reading
+    /// status, priority and rating of `other` are only taken over where this paper has none.
+    pub fn absorb(&mut self, other: LocalPaper) {
+        if self.note.is_empty() {
+            self.note = other.note;
+        }
+        for attachment in other.attachments {
+            self.attach(attachment);
+        }
+        for ee in other.ees {
+            if !self.ees.contains(&ee) {
+                self.ees.push(ee);
+            }
+        }
+        for tag in &other.tags {
+            self.add_tag(tag);
+        }
+        for name in &other.collections {
+            self.add_to_collection(name);
+        }
+        self.status = self.status.max(other.status);
+        if self.priority == Priority::Normal {
+            self.priority = other.priority;
+        }
+        self.rating = self.rating.or(other.rating);
+    }
+
+    pub fn metadata(&self) -> &PaperInfo {
+        &self.metadata
+    }
+    pub fn remote_tag(&self) -> String {
+        let tags = self
+            .tags
+            .iter()
+            .map(|t| format!(" #{}", t))
+            .collect::<String>();
+        let priority = match self.priority {
+            Priority::Normal => String::new(),
+            priority => format!(" !{}", priority),
+        };
+        let rating = self.rating.map(|r| format!(" {}", r)).unwrap_or_default();
+        style(format!(
+            "Local({} {}{} {}{}{})",
+            self.metadata().year,
+            self.metadata().venue,
+            tags,
+            self.status,
+            priority,
+            rating
+        ))
+        .red()
+        .bold()
+        .to_string()
+    }
+}
+
+fn contains_ignore_case(names: &[String], name: &str) -> bool {
+    names.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+fn add_ignore_case(names: &mut Vec<String>, name: &str) -> bool {
+    if contains_ignore_case(names, name) {
+        false
+    } else {
+        names.push(name.to_owned());
+        true
+    }
+}
+
+fn remove_ignore_case(names: &mut Vec<String>, name: &str) -> bool {
+    let len = names.len();
+    names.retain(|n| !n.eq_ignore_ascii_case(name));
+    names.len() != len
+}
+
+impl Display for LocalPaper {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.metadata, self.remote_tag())
+    }
+}
+
+#[derive(Debug)]
+pub struct Library {
+    papers: Vec<LocalPaper>,
+    index: Index,
+    modified: bool,
+    store: Box<dyn Store>,
+    data_dir: PathBuf,
+    // papers changed or removed since the last save, used to merge with changes of other processes
+    touched: Vec<PaperInfo>,
+    removed: Vec<PaperInfo>,
+    /// Number of snapshots kept in `data_dir/backups`, none if zero.
+    backups: usize,
+    /// Operations since the last save, appended to the journal on save
+    journal: Vec<(OperationKind, Vec<Change>)>,
+}
+
+impl Library {
+    pub const CURRENT_VERSION: LibraryVersion = LibraryVersion(7);
+
+    pub fn open(config: &Config) -> Result<Library> {
+        let mut lib = Self::open_with(config.backend, &config.data_dir)?;
+        lib.backups = config.backups;
+        Ok(lib)
+    }
+
+    pub fn open_with(backend: Backend, data_dir: &Path) -> Result<Library> {
+        let mut store = open_store(backend, data_dir)?;
+        let papers = {
+            let _lock = StoreLock::acquire(data_dir)?;
+            store.load()?
+        };
+        let mut lib = Library {
+            papers,
+            index: Index::default(),
+            modified: false,
+            store,
+            data_dir: data_dir.to_path_buf(),
+            touched: vec![],
+            removed: vec![],
+            backups: backup::DEFAULT_KEEP,
+            journal: vec![],
+        };
+        lib.assign_citekeys();
+        lib.rebuild_index();
+        Ok(lib)
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        if !self.modified {
+            return Ok(());
+        }
+
+        log::warn!("Saving library...");
+        let _lock = StoreLock::acquire(&self.data_dir)?;
+        if self.store.changed()? {
+            log::warn!("Library was modified by another process, merging changes...");
+            let on_disk = self.store.load()?;
+            self.merge(on_disk);
+        }
+        if self.backups > 0 && backup::list(&self.data_dir)?.is_empty() {
+            // keep the state from before the first save, e.g. of an older version of xivar
+            let before = self.store.load()?;
+            if !before.is_empty() {
+                backup::write(&self.data_dir, &before, self.backups)?;
+            }
+        }
+        self.store.save(&self.papers)?;
+        if self.backups > 0 {
+            backup::write(&self.data_dir, &self.papers, self.backups)?;
+        }
+        journal::append(&self.data_dir, std::mem::take(&mut self.journal))?;
+        log::warn!("... done!");
+
+        self.touched.clear();
+        self.removed.clear();
+        self.modified = false;
+        Ok(())
+    }
+
+    /// Takes over the changes which other processes saved since the last load or save, e.g.
+    /// while `xivar watch` is running. Changes of this session which are not saved yet are kept.
+    pub fn reload(&mut self) -> Result<()> {
+        let _lock = StoreLock::acquire(&self.data_dir)?;
+        if self.store.changed()? {
+            let on_disk = self.store.load()?;
+            self.merge(on_disk);
+        }
+        Ok(())
+    }
+
+    /// Applies the changes of this session on top of `on_disk`.
+    fn merge(&mut self, on_disk: Vec<LocalPaper>) {
+        let mut ours: Vec<LocalPaper> = self
+            .papers
+            .drain(..)
+            .filter(|p| self.touched.contains(p.metadata()))
+            .collect();
+        let mut merged: Vec<LocalPaper> = on_disk
+            .into_iter()
+            .filter(|p| !self.removed.contains(p.metadata()))
+            .map(
+                |p| match ours.iter().position(|o| o.metadata() == p.metadata()) {
+                    Some(i) => ours.swap_remove(i),
+                    None => p,
+                },
+            )
+            .collect();
+        merged.append(&mut ours);
+        self.papers = merged;
+        self.assign_citekeys();
+        self.rebuild_index();
+    }
+
+    fn record(&mut self, kind: OperationKind, changes: Vec<Change>) {
+        let changes: Vec<Change> = changes
+            .into_iter()
+            .filter(|c| c.before != c.after)
+            .collect();
+        if !changes.is_empty() {
+            self.journal.push((kind, changes));
+        }
+    }
+
+    fn touch(&mut self, info: &PaperInfo) {
+        self.removed.retain(|p| p != info);
+        if !self.touched.contains(info) {
+            self.touched.push(info.clone());
+        }
+    }
+
+    fn forget(&mut self, info: &PaperInfo) {
+        self.touched.retain(|p| p != info);
+        if !self.removed.contains(info) {
+            self.removed.push(info.clone());
+        }
+    }
+
+    pub fn add(&mut self, mut paper: LocalPaper) {
+        self.touch(paper.metadata());
+        paper.update_hashes();
+        let change = match self
+            .papers
+            .iter()
+            .position(|p| p.metadata() == paper.metadata())
+        {
+            None => {
+                if !citekey::is_valid(&paper.citekey) {
+                    paper.citekey = citekey::generate(paper.metadata());
+                }
+                paper.citekey = citekey::disambiguate(&paper.citekey, |key| {
+                    self.papers.iter().any(|p| p.citekey == key)
+                });
+                self.index.insert(self.papers.len(), paper.tokens());
+                self.papers.push(paper.clone());
+                Change {
+                    before: None,
+                    after: Some(paper),
+                }
+            }
+            Some(i) => {
+                let before = self.papers[i].clone();
+                self.papers[i].absorb(paper);
+                self.index.insert(i, self.papers[i].tokens());
+                Change {
+                    before: Some(before),
+                    after: Some(self.papers[i].clone()),
+                }
+            }
+        };
+        self.record(OperationKind::Add, vec![change]);
+        self.modified = true;
+    }
+
+    /// Replaces the entry of the same paper by `paper`. Returns `false` if there is no such entry.
+    pub fn update(&mut self, mut paper: LocalPaper) -> bool {
+        paper.update_hashes();
+        match self
+            .papers
+            .iter()
+            .position(|p| p.metadata() == paper.metadata())
+        {
+            Some(i) => {
+                self.touch(paper.metadata());
+                self.index.remove(i, self.papers[i].tokens());
+                self.index.insert(i, paper.tokens());
+                let before = std::mem::replace(&mut self.papers[i], paper.clone());
+                self.record(
+                    OperationKind::Edit,
+                    vec![Change {
+                        before: Some(before),
+                        after: Some(paper),
+                    }],
+                );
+                self.modified = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // pub fn remove(&mut self, paper: &Paper) -> bool {
+    //     if let Some(idx) = self.papers.iter().position(|p| p == paper) {
+    //         self.papers.swap_remove(idx);
+    //         self.modified = true;
+    //         return true;
+    //     }
+
+    //     false
+    // }
+
+    pub fn iter_matches<'a>(&'a self, query: &'a Query) -> impl Iterator<Item = &'a LocalPaper> {
+        let positions: Box<dyn Iterator<Item = usize>> = match self.index.search(query.text_terms())
+        {
+            Some(positions) => Box::new(positions.into_iter()),
+            None => Box::new(0..self.papers.len()),
+        };
+        positions
+            .map(move |i| &self.papers[i])
+            .filter(move |paper| paper.matches_filters(query))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LocalPaper> {
+        self.papers.iter()
+    }
+
+    pub fn size(&self) -> usize {
+        self.papers.len()
+    }
+
+    pub fn find_paper_by_path<'a>(&'a self, path: &Path) -> Option<&'a LocalPaper> {
+        self.papers
+            .iter()
+            .find(|paper| paper.attachments.iter().any(|a| a.path == path))
+    }
+
+    /// Removes all attachments whose files do not exist anymore, and all papers whose last
+    /// attachment was removed. Papers which never had an attachment, e.g. imported from a
+    /// bibliography, are kept.
+    pub fn clean(&mut self) -> Vec<Attachment> {
+        let mut removed = vec![];
+        let mut changes = vec![];
+        let mut emptied = vec![];
+        for paper in &mut self.papers {
+            let before = paper.clone();
+            let (kept, missing): (Vec<Attachment>, Vec<Attachment>) =
+                paper.attachments.drain(..).partition(|a| a.exists());
+            paper.attachments = kept;
+            if !missing.is_empty() {
+                let after = Some(paper.clone()).filter(|p| !p.attachments.is_empty());
+                if after.is_none() {
+                    emptied.push(paper.metadata().clone());
+                }
+                changes.push(Change {
+                    before: Some(before),
+                    after,
+                });
+                removed.extend(missing);
+            }
+        }
+        self.papers
+            .retain(|paper| !emptied.contains(paper.metadata()));
+        let changed: Vec<PaperInfo> = changes
+            .iter()
+            .filter_map(|c| c.after.as_ref())
+            .map(|p| p.metadata().clone())
+            .collect();
+        for info in &changed {
+            self.touch(info);
+        }
+        for info in &emptied {
+            self.forget(info);
+        }
+        self.record(OperationKind::Remove, changes);
+        self.rebuild_index();
+        self.modified = true;
+        removed
+    }
+
+    /// Replaces the whole library by `papers`, e.g. from a backup.
+    pub fn restore(&mut self, papers: Vec<LocalPaper>) {
+        let mut changes = vec![];
+        for paper in std::mem::take(&mut self.papers) {
+            self.forget(paper.metadata());
+            let after = papers
+                .iter()
+                .find(|p| p.metadata() == paper.metadata())
+                .cloned();
+            changes.push(Change {
+                before: Some(paper),
+                after,
+            });
+        }
+        for paper in &papers {
+            self.touch(paper.metadata());
+            if !changes
+                .iter()
+                .any(|c| c.after.as_ref().map(LocalPaper::metadata) == Some(paper.metadata()))
+            {
+                changes.push(Change {
+                    before: None,
+                    after: Some(paper.clone()),
+                });
+            }
+        }
+        self.record(OperationKind::Restore, changes);
+        self.papers = papers;
+        self.assign_citekeys();
+        self.rebuild_index();
+        self.modified = true;
+    }
+
+    pub fn clear(&mut self) -> Vec<LocalPaper> {
+        let removed: Vec<LocalPaper> = self.papers.drain(..).collect();
+        for paper in &removed {
+            self.forget(paper.metadata());
+        }
+        let changes = removed
+            .iter()
+            .map(|paper| Change {
+                before: Some(paper.clone()),
+                after: None,
+            })
+            .collect();
+        self.record(OperationKind::Remove, changes);
+        self.index.clear();
+        self.modified = true;
+        removed
+    }
+
+    /// Drops the operations recorded since the last save from the journal, e.g. when copying
+    /// the library to another backend.
+    pub fn forget_history(&mut self) {
+        self.journal.clear();
+    }
+
+    /// Saves and undoes the last `n` operations of the journal which have not been undone yet,
+    /// and moves the files of organize operations back. Returns the undone operations, each with
+    /// the papers which had been changed since.
+    pub fn undo(&mut self, n: usize) -> Result<Vec<(Operation, Vec<PaperInfo>)>> {
+        self.save()?;
+        let ops = journal::read(&self.data_dir)?;
+        let mut undone = vec![];
+        for op in journal::undoable(&ops, n) {
+            if op.kind == OperationKind::Organize {
+                if let Err(err) = relocate::move_back(op) {
+                    // the operations undone so far are kept
+                    self.save()?;
+                    return Err(err);
+                }
+            }
+            undone.push((op.clone(), self.revert(op)));
+        }
+        self.save()?;
+        Ok(undone)
+    }
+
+    /// Undoes `op` by restoring the papers it changed to their previous state.
+    /// Returns the papers which have been changed since by other operations.
+    fn revert(&mut self, op: &Operation) -> Vec<PaperInfo> {
+        let mut conflicts = vec![];
+        let mut changes = vec![];
+        for change in op.changes.iter().rev() {
+            let current = change
+                .after
+                .as_ref()
+                .or(change.before.as_ref())
+                .and_then(|paper| {
+                    self.papers
+                        .iter()
+                        .position(|p| p.metadata() == paper.metadata())
+                });
+            let current = current.map(|i| self.papers.remove(i));
+            if current != change.after {
+                if let Some(paper) = change.after.as_ref().or(change.before.as_ref()) {
+                    conflicts.push(paper.metadata().clone());
+                }
+            }
+            if let Some(paper) = &current {
+                self.forget(paper.metadata());
+            }
+            if let Some(paper) = &change.before {
+                self.touch(paper.metadata());
+                self.papers.push(paper.clone());
+            }
+            changes.push(Change {
+                before: current,
+                after: change.before.clone(),
+            });
+        }
+        self.record(OperationKind::Revert(op.id), changes);
+        self.assign_citekeys();
+        self.rebuild_index();
+        self.modified = true;
+        conflicts
+    }
+
+    /// Gives every paper the citation key after `template`, see `citekey::generate_all`.
+    pub fn set_citekeys(&mut self, template: &Template) -> Result<()> {
+        let keys = citekey::generate_all(&self.papers, template)?;
+        let mut changes = vec![];
+        for (i, key) in keys.into_iter().enumerate() {
+            if self.papers[i].citekey != key {
+                let before = self.papers[i].clone();
+                self.papers[i].citekey = key;
+                self.touch(before.metadata());
+                changes.push(Change {
+                    before: Some(before),
+                    after: Some(self.papers[i].clone()),
+                });
+            }
+        }
+        if !changes.is_empty() {
+            self.record(OperationKind::Edit, changes);
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    /// Gives papers without a valid citation key or with the key of a previous paper a new unique one.
+    fn assign_citekeys(&mut self) {
+        let mut taken = std::collections::HashSet::new();
+        for i in 0..self.papers.len() {
+            let paper = &self.papers[i];
+            if !citekey::is_valid(&paper.citekey) || taken.contains(&paper.citekey) {
+                let key = if !citekey::is_valid(&paper.citekey) {
+                    citekey::generate(paper.metadata())
+                } else {
+                    paper.citekey.clone()
+                };
+                // keys of later papers are kept, so they must not be taken either
+                let key = citekey::disambiguate(&key, |key| {
+                    taken.contains(key) || self.papers[i + 1..].iter().any(|p| p.citekey == key)
+                });
+                self.papers[i].citekey = key;
+            }
+            taken.insert(self.papers[i].citekey.clone());
+        }
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index.clear();
+        for (i, paper) in self.papers.iter().enumerate() {
+            self.index.insert(i, paper.tokens());
+        }
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            println!("Error: {}", e)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct LibraryVersion(pub u32);
@@ -0,0 +1,112 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// How far the paper has been read.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingStatus {
+    #[default]
+    Unread,
+    Reading,
+    Read,
+}
+
+impl ReadingStatus {
+    /// The next status in the cycle unread → reading → read → unread.
+    pub fn next(self) -> Self {
+        match self {
+            ReadingStatus::Unread => ReadingStatus::Reading,
+            ReadingStatus::Reading => ReadingStatus::Read,
+            ReadingStatus::Read => ReadingStatus::Unread,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "unread" => Some(ReadingStatus::Unread),
+            "reading" => Some(ReadingStatus::Reading),
+            "read" => Some(ReadingStatus::Read),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ReadingStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadingStatus::Unread => write!(f, "unread"),
+            ReadingStatus::Reading => write!(f, "reading"),
+            ReadingStatus::Read => write!(f, "read"),
+        }
+    }
+}
+
+/// Position of the paper in the reading queue.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    /// The next priority in the cycle normal → high → low → normal.
+    pub fn next(self) -> Self {
+        match self {
+            Priority::Normal => Priority::High,
+            Priority::High => Priority::Low,
+            Priority::Low => Priority::Normal,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(Priority::Low),
+            "normal" => Some(Priority::Normal),
+            "high" => Some(Priority::High),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::Low => write!(f, "low"),
+            Priority::Normal => write!(f, "normal"),
+            Priority::High => write!(f, "high"),
+        }
+    }
+}
+
+/// Rating from 1 to 5 stars.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rating(u8);
+
+impl Rating {
+    pub const MAX: u8 = 5;
+
+    pub fn new(stars: u8) -> Option<Self> {
+        if (1..=Self::MAX).contains(&stars) {
+            Some(Rating(stars))
+        } else {
+            None
+        }
+    }
+
+    pub fn stars(&self) -> u8 {
+        self.0
+    }
+}
+
+impl Display for Rating {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for i in 1..=Self::MAX {
+            write!(f, "{}", if i <= self.0 { '★' } else { '☆' })?;
+        }
+        Ok(())
+    }
+}
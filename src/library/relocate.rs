@@ -0,0 +1,207 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+
+use super::{
+    journal::{Change, Operation, OperationKind},
+    ContentHash, Library,
+};
+use crate::{
+    util::{move_file, visit_files},
+    PaperInfo,
+};
+
+/// Outcome of `Library::relocate`.
+#[derive(Debug, Default)]
+pub struct Relocation {
+    /// Files found at a new location, as (paper, old path, new path).
+    pub moved: Vec<(PaperInfo, PathBuf, PathBuf)>,
+    /// Files which could not be found anywhere.
+    pub missing: Vec<(PaperInfo, PathBuf)>,
+    /// Number of existing files which were hashed for the first time.
+    pub hashed: usize,
+}
+
+impl Library {
+    /// Hashes all existing files which have not been hashed yet. Returns the number of new hashes.
+    pub fn update_hashes(&mut self) -> usize {
+        let mut hashed = 0;
+        for i in 0..self.papers.len() {
+            let n = self.papers[i].update_hashes();
+            if n > 0 {
+                hashed += n;
+                let info = self.papers[i].metadata().clone();
+                self.touch(&info);
+                self.modified = true;
+            }
+        }
+        hashed
+    }
+
+    /// Searches `roots` for missing files by their content hash and re-links the papers to the
+    /// found files. With `dry_run`, the library is left unchanged.
+    pub fn relocate(&mut self, roots: &[PathBuf], dry_run: bool) -> Relocation {
+        let mut relocation = Relocation {
+            hashed: if dry_run {
+                self.iter()
+                    .flat_map(|p| &p.attachments)
+                    .filter(|a| a.hash.is_none() && a.exists())
+                    .count()
+            } else {
+                self.update_hashes()
+            },
+            ..Default::default()
+        };
+
+        let mut lost: Vec<(usize, usize, ContentHash)> = vec![];
+        for (i, paper) in self.papers.iter().enumerate() {
+            for (j, attachment) in paper.attachments.iter().enumerate() {
+                if attachment.exists() {
+                    continue;
+                }
+                match attachment.hash {
+                    Some(hash) => lost.push((i, j, hash)),
+                    None => relocation
+                        .missing
+                        .push((paper.metadata().clone(), attachment.path.clone())),
+                }
+            }
+        }
+        if lost.is_empty() {
+            return relocation;
+        }
+
+        let sizes: HashSet<u64> = lost.iter().map(|(_, _, hash)| hash.size).collect();
+        let hashes: HashSet<ContentHash> = lost.iter().map(|(_, _, hash)| *hash).collect();
+        let known: HashSet<PathBuf> = self
+            .papers
+            .iter()
+            .flat_map(|p| p.attachments.iter().map(|a| a.path.clone()))
+            .collect();
+
+        let mut found: HashMap<ContentHash, PathBuf> = HashMap::new();
+        for root in roots.iter().filter(|r| !r.as_os_str().is_empty()) {
+            let root = match root.canonicalize() {
+                Ok(root) => root,
+                Err(err) => {
+                    log::warn!("Could not find root {:?}: {}", root, err);
+                    continue;
+                }
+            };
+            visit_files(&root, &mut |path, size| {
+                if !sizes.contains(&size) || known.contains(path) {
+                    return;
+                }
+                match ContentHash::of_file(path) {
+                    Ok(hash) if hashes.contains(&hash) => {
+                        found.entry(hash).or_insert_with(|| path.to_path_buf());
+                    }
+                    Ok(_) => {}
+                    Err(err) => log::warn!("Could not hash {:?}: {}", path, err),
+                }
+            });
+        }
+
+        let mut changes: Vec<Change> = vec![];
+        for (i, j, hash) in lost {
+            let info = self.papers[i].metadata().clone();
+            let old = self.papers[i].attachments[j].path.clone();
+            match found.get(&hash) {
+                Some(new) => {
+                    if !dry_run {
+                        let before = self.papers[i].clone();
+                        self.papers[i].attachments[j].path = new.clone();
+                        // several files of the same paper may have moved
+                        match changes
+                            .iter_mut()
+                            .find(|c| c.before.as_ref().map(|p| p.metadata()) == Some(&info))
+                        {
+                            Some(change) => change.after = Some(self.papers[i].clone()),
+                            None => changes.push(Change {
+                                before: Some(before),
+                                after: Some(self.papers[i].clone()),
+                            }),
+                        }
+                        self.touch(&info);
+                        self.modified = true;
+                    }
+                    relocation.moved.push((info, old, new.clone()));
+                }
+                None => relocation.missing.push((info, old)),
+            }
+        }
+        self.record(OperationKind::Relocate, changes);
+        relocation
+    }
+
+    /// Points the files at the old paths of `moved`, given as (paper, old path, new path), to
+    /// their new paths, recorded as a single organize operation.
+    pub fn relink(&mut self, moved: &[(PaperInfo, PathBuf, PathBuf)]) {
+        let mut changes: Vec<Change> = vec![];
+        for (info, old, new) in moved {
+            let i = match self.papers.iter().position(|p| p.metadata() == info) {
+                Some(i) => i,
+                None => continue,
+            };
+            let before = self.papers[i].clone();
+            let attachment = match self.papers[i]
+                .attachments
+                .iter_mut()
+                .find(|a| &a.path == old)
+            {
+                Some(attachment) => attachment,
+                None => continue,
+            };
+            attachment.path = new.clone();
+            match changes
+                .iter_mut()
+                .find(|c| c.before.as_ref().map(|p| p.metadata()) == Some(info))
+            {
+                Some(change) => change.after = Some(self.papers[i].clone()),
+                None => changes.push(Change {
+                    before: Some(before),
+                    after: Some(self.papers[i].clone()),
+                }),
+            }
+            self.touch(info);
+            self.modified = true;
+        }
+        self.record(OperationKind::Organize, changes);
+    }
+}
+
+/// Moves the files which the organize operation `op` moved back to their previous paths. If a
+/// file cannot be moved back, the files moved back so far are moved again, so that either all
+/// files or none are back.
+pub(super) fn move_back(op: &Operation) -> Result<()> {
+    let mut moved: Vec<(&Path, &Path)> = vec![];
+    for change in &op.changes {
+        let (before, after) = match (&change.before, &change.after) {
+            (Some(before), Some(after)) => (before, after),
+            _ => continue,
+        };
+        for (old, new) in before.attachments.iter().zip(&after.attachments) {
+            // a file removed since is missing either way
+            if old.path == new.path || !new.path.exists() {
+                continue;
+            }
+            if let Err(err) = move_file(&new.path, &old.path) {
+                for (from, to) in moved.into_iter().rev() {
+                    if let Err(err) = move_file(to, from) {
+                        log::warn!("{:#}", err);
+                    }
+                }
+                bail!(
+                    "could not move the files of operation {} back: {:#}",
+                    op.id,
+                    err
+                );
+            }
+            moved.push((&new.path, &old.path));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, Transaction};
+
+use super::{migrations, store::Store, Library, LibraryVersion, LocalPaper};
+
+/// The library stored in an SQLite database `lib.sqlite`.
+///
+/// Every paper is kept as a bincode blob next to indexed columns of its
+/// identifier, title, year, venue, default location and authors.
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Connection,
+    path: PathBuf,
+    data_version: u32,
+}
+
+impl SqliteStore {
+    pub fn open(data_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(data_dir).with_context(|| {
+            format!("unable to create data directory: {}", data_dir.display())
+        })?;
+        let path = data_dir.join("lib.sqlite");
+        let conn = Connection::open(&path)
+            .with_context(|| format!("could not open store: {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS papers (
+                id INTEGER PRIMARY KEY,
+                identifier TEXT,
+                title TEXT NOT NULL,
+                year TEXT NOT NULL,
+                venue TEXT NOT NULL,
+                location TEXT NOT NULL,
+                data BLOB NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS authors (
+                paper INTEGER NOT NULL REFERENCES papers(id),
+                name TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS papers_identifier ON papers(identifier);
+            CREATE INDEX IF NOT EXISTS papers_title ON papers(title);
+            CREATE INDEX IF NOT EXISTS papers_year ON papers(year);
+            CREATE INDEX IF NOT EXISTS papers_location ON papers(location);
+            CREATE INDEX IF NOT EXISTS authors_name ON authors(name);
+            CREATE INDEX IF NOT EXISTS authors_paper ON authors(paper);",
+        )
+        .with_context(|| format!("could not create tables in store: {}", path.display()))?;
+        Ok(SqliteStore {
+            conn,
+            path,
+            data_version: 0,
+        })
+    }
+
+    /// Changes whenever another connection commits to the database.
+    fn data_version(&self) -> Result<u32> {
+        Ok(self
+            .conn
+            .query_row("PRAGMA data_version", [], |row| row.get(0))?)
+    }
+
+    fn version(&self) -> Result<LibraryVersion> {
+        let version: u32 = self
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        Ok(LibraryVersion(version))
+    }
+}
+
+impl Store for SqliteStore {
+    fn load(&mut self) -> Result<Vec<LocalPaper>> {
+        let mut version = self.version()?;
+        if version.0 == 0 {
+            // freshly created database
+            self.conn
+                .pragma_update(None, "user_version", Library::CURRENT_VERSION.0)?;
+            version = Library::CURRENT_VERSION;
+        }
+
+        let papers = {
+            let mut stmt = self.conn.prepare("SELECT data FROM papers ORDER BY id")?;
+            let blobs = stmt
+                .query_map([], |row| row.get::<_, Vec<u8>>(0))?
+                .collect::<rusqlite::Result<Vec<Vec<u8>>>>()?;
+            blobs
+                .iter()
+                .map(|blob| migrations::load_paper(version, blob))
+                .collect::<Result<Vec<LocalPaper>>>()
+                .with_context(|| format!("could not deserialize store: {}", self.path.display()))?
+        };
+
+        if version != Library::CURRENT_VERSION {
+            let backup = migrations::backup(&self.path, version)?;
+            log::warn!(
+                "Migrating library from version {} to {}. Backup saved to {}",
+                version.0,
+                Library::CURRENT_VERSION.0,
+                backup.display()
+            );
+            let tx = self.conn.transaction()?;
+            tx.execute("DELETE FROM authors", [])?;
+            tx.execute("DELETE FROM papers", [])?;
+            write_rows(&tx, &papers)?;
+            tx.pragma_update(None, "user_version", Library::CURRENT_VERSION.0)?;
+            tx.commit()
+                .with_context(|| format!("could not migrate store: {}", self.path.display()))?;
+        }
+
+        self.data_version = self.data_version()?;
+        Ok(papers)
+    }
+
+    fn save(&mut self, papers: &[LocalPaper]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+
+        // Only touch rows whose content actually changed.
+        let mut stored: HashMap<Vec<u8>, Vec<i64>> = HashMap::new();
+        {
+            let mut stmt = tx.prepare("SELECT id, data FROM papers")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            for row in rows {
+                let (id, data): (i64, Vec<u8>) = row?;
+                stored.entry(data).or_default().push(id);
+            }
+        }
+
+        let mut to_insert = vec![];
+        for paper in papers {
+            let data = bincode::serialize(paper).context("could not serialize paper")?;
+            if stored.get_mut(&data).and_then(|ids| ids.pop()).is_none() {
+                to_insert.push(paper);
+            }
+        }
+
+        for id in stored.into_values().flatten() {
+            tx.execute("DELETE FROM authors WHERE paper = ?1", params![id])?;
+            tx.execute("DELETE FROM papers WHERE id = ?1", params![id])?;
+        }
+
+        write_rows(&tx, to_insert)?;
+
+        tx.commit()
+            .with_context(|| format!("could not write to store: {}", self.path.display()))?;
+        self.data_version = self.data_version()?;
+        Ok(())
+    }
+
+    fn changed(&self) -> Result<bool> {
+        Ok(self.data_version()? != self.data_version)
+    }
+}
+
+/// Inserts `papers` together with their indexed columns and authors.
+fn write_rows<'a>(
+    tx: &Transaction,
+    papers: impl IntoIterator<Item = &'a LocalPaper>,
+) -> Result<()> {
+    for paper in papers {
+        let data = bincode::serialize(paper).context("could not serialize paper")?;
+        let info = paper.metadata();
+        tx.execute(
+            "INSERT INTO papers (identifier, title, year, venue, location, data)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                info.id.as_ref().map(|id| id.to_prefixed_string()),
+                info.title.normalized(),
+                info.year,
+                info.venue.to_string(),
+                paper
+                    .location()
+                    .map(|l| l.to_string_lossy())
+                    .unwrap_or_default(),
+                data
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        for author in &info.authors {
+            tx.execute(
+                "INSERT INTO authors (paper, name) VALUES (?1, ?2)",
+                params![id, author],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SqliteStore::open(dir.path()).unwrap();
+        store
+            .conn
+            .execute(
+                "INSERT INTO papers (title, year, venue, location, data)
+                VALUES ('', '', '', '', ?1)",
+                params![&include_bytes!("fixtures/v6.paper")[..]],
+            )
+            .unwrap();
+        store.conn.pragma_update(None, "user_version", 6).unwrap();
+
+        let papers = store.load().unwrap();
+        assert_eq!(papers.len(), 1);
+        assert_eq!(store.version().unwrap(), Library::CURRENT_VERSION);
+        let (title, authors): (String, u32) = store
+            .conn
+            .query_row(
+                "SELECT title, (SELECT COUNT(*) FROM authors WHERE paper = papers.id)
+                FROM papers",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(title, papers[0].metadata().title.normalized());
+        assert_eq!(authors as usize, papers[0].metadata().authors.len());
+        assert_eq!(
+            SqliteStore::open(dir.path()).unwrap().load().unwrap(),
+            papers
+        );
+    }
+}
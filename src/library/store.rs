@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use super::{bincode_store::BincodeStore, files_store::FilesStore, LocalPaper};
+
+/// Persistent storage of the library.
+pub trait Store: std::fmt::Debug + Send {
+    /// Loads all papers, upgrading older layouts if necessary.
+    fn load(&mut self) -> Result<Vec<LocalPaper>>;
+
+    /// Replaces the stored papers by `papers`.
+    fn save(&mut self, papers: &[LocalPaper]) -> Result<()>;
+
+    /// Whether another process modified the store since the last `load` or `save`.
+    fn changed(&self) -> Result<bool>;
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Bincode,
+    Sqlite,
+    /// One TOML file per paper.
+    Files,
+}
+
+pub fn open_store(backend: Backend, data_dir: &Path) -> Result<Box<dyn Store>> {
+    match backend {
+        Backend::Bincode => Ok(Box::new(BincodeStore::new(data_dir))),
+        Backend::Files => Ok(Box::new(FilesStore::new(data_dir))),
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => Ok(Box::new(super::sqlite_store::SqliteStore::open(data_dir)?)),
+        #[cfg(not(feature = "sqlite"))]
+        Backend::Sqlite => {
+            anyhow::bail!("xivar was built without SQLite support, enable the `sqlite` feature")
+        }
+    }
+}
@@ -1,17 +1,53 @@
+mod add;
+mod attach;
+mod backup;
 mod clean;
+mod convert;
+mod dedup;
+mod export;
+mod formats;
+mod history;
 mod identifier;
+mod identify;
+mod import;
+mod import_dir;
+mod inbox;
 mod interactive;
 mod library;
+mod note;
+mod organize;
 mod paper;
+mod pdf;
 mod query;
 mod remotes;
+mod resolve;
+mod stats;
+mod tag;
+mod template;
+mod undo;
 mod util;
+mod watch;
 mod xiv_config;
 
+use add::Add;
+use attach::Attach;
+use backup::Backup;
 use clean::Clean;
+use convert::Convert;
+use dedup::Dedup;
+use export::Export;
+use history::History;
 pub use identifier::*;
+use import::Import;
+use inbox::Inbox;
+use note::Note;
+use organize::Organize;
 pub use paper::*;
 pub use query::Query;
+use stats::Stats;
+use tag::Tag;
+use undo::Undo;
+use watch::Watch;
 
 use clap::Parser;
 use clap::Subcommand;
@@ -44,7 +80,7 @@ pub struct App {
 impl App {
     pub async fn run(&self, config: xiv_config::Config) -> Result<()> {
         if let Some(helper) = &self.helper {
-            helper.run(config)
+            helper.run(config).await
         } else {
             interactive::interactive(config).await
         }
@@ -53,13 +89,43 @@ impl App {
 
 #[derive(Subcommand, Debug)]
 pub enum Helpers {
+    Add(Add),
+    Attach(Attach),
+    Backup(Backup),
     Clean(Clean),
+    Convert(Convert),
+    Dedup(Dedup),
+    Export(Export),
+    History(History),
+    Import(Import),
+    Inbox(Inbox),
+    Note(Note),
+    Organize(Organize),
+    Stats(Stats),
+    Tag(Tag),
+    Undo(Undo),
+    Watch(Watch),
 }
 
 impl Helpers {
-    fn run(&self, config: xiv_config::Config) -> Result<()> {
+    async fn run(&self, config: xiv_config::Config) -> Result<()> {
         match &self {
+            Helpers::Add(h) => h.run(config).await,
+            Helpers::Attach(h) => h.run(config),
+            Helpers::Backup(h) => h.run(config),
             Helpers::Clean(h) => h.run(config),
+            Helpers::Convert(h) => h.run(config),
+            Helpers::Dedup(h) => h.run(config),
+            Helpers::Export(h) => h.run(config),
+            Helpers::History(h) => h.run(config),
+            Helpers::Import(h) => h.run(config).await,
+            Helpers::Inbox(h) => h.run(config),
+            Helpers::Note(h) => h.run(config),
+            Helpers::Organize(h) => h.run(config),
+            Helpers::Stats(h) => h.run(config),
+            Helpers::Tag(h) => h.run(config),
+            Helpers::Undo(h) => h.run(config),
+            Helpers::Watch(h) => h.run(config).await,
         }
     }
 }
@@ -0,0 +1,49 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::{
+    library::{Library, LocalPaper},
+    util::{edit_in_editor, select_paper},
+    xiv_config::Config,
+    Query,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Edit the notes of a paper of your library in $EDITOR")]
+pub struct Note {
+    query: Vec<String>,
+
+    /// Print the notes instead of editing them
+    #[clap(short, long)]
+    show: bool,
+}
+
+impl Note {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let mut lib = Library::open(&config)?;
+
+        let query = Query::from(self.query.join(" "));
+        let mut matches: Vec<LocalPaper> = lib.iter_matches(&query).cloned().collect();
+        let mut paper = match matches.len() {
+            0 => bail!("No paper in your library matches the query!"),
+            1 => matches.remove(0),
+            _ => {
+                let i = select_paper(&matches)?;
+                matches.remove(i)
+            }
+        };
+
+        if self.show {
+            println!("{}\n\n{}", paper.metadata(), paper.note);
+            return Ok(());
+        }
+
+        let note = edit_in_editor(&paper.note)?;
+        if note != paper.note {
+            paper.note = note;
+            lib.update(paper);
+            println!("Saved notes.");
+        }
+        lib.save()
+    }
+}
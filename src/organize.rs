@@ -0,0 +1,243 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use crate::{
+    library::{Library, LocalPaper},
+    template::Template,
+    util::{confirm, move_file, unique_path},
+    xiv_config::Config,
+    PaperInfo, Query,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Rename and move the files of your library into your document directory")]
+pub struct Organize {
+    /// Papers whose files are moved, all papers if omitted
+    query: Vec<String>,
+
+    /// Path of the files relative to the document directory, e.g. `{year}/{venue}/{citekey}`,
+    /// defaults to the `filename` setting
+    #[clap(long, value_name = "TEMPLATE")]
+    template: Option<Template>,
+
+    /// Only print the planned moves without changing anything
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Move the files without asking
+    #[clap(short, long)]
+    yes: bool,
+}
+
+/// A file of a paper which is moved from `from` to `to`.
+#[derive(Debug)]
+struct Move {
+    info: PaperInfo,
+    from: PathBuf,
+    to: PathBuf,
+}
+
+/// The files which are moved, and how many are in place already or missing.
+#[derive(Debug, Default)]
+struct Plan {
+    moves: Vec<Move>,
+    in_place: usize,
+    missing: Vec<PathBuf>,
+}
+
+impl Organize {
+    pub fn run(&self, config: Config) -> Result<()> {
+        if config.paper_dir.as_os_str().is_empty() {
+            bail!("Set `document_dir` in xivar.toml to organize your library");
+        }
+        let root = config.paper_dir.canonicalize().with_context(|| {
+            format!(
+                "could not find document directory {}",
+                config.paper_dir.display()
+            )
+        })?;
+        let template = self.template.as_ref().unwrap_or(&config.filename);
+
+        let mut lib = Library::open(&config)?;
+        let query = Query::from(self.query.join(" "));
+        let papers: Vec<&LocalPaper> = lib.iter_matches(&query).collect();
+        let plan = plan(&papers, template, &root);
+
+        for path in &plan.missing {
+            println!("Skipping missing file {}", path.display());
+        }
+        for m in &plan.moves {
+            println!("{} -> {}", m.from.display(), m.to.display());
+        }
+        println!(
+            "{} files to move, {} already in place, {} missing.",
+            plan.moves.len(),
+            plan.in_place,
+            plan.missing.len()
+        );
+        if self.dry_run || plan.moves.is_empty() {
+            return Ok(());
+        }
+        if !self.yes && !confirm(&format!("Move {} files?", plan.moves.len()))? {
+            return Ok(());
+        }
+
+        let total = plan.moves.len();
+        let mut moved = vec![];
+        let mut failure = None;
+        for m in plan.moves {
+            match move_file(&m.from, &m.to) {
+                Ok(()) => moved.push((m.info, m.from, m.to)),
+                Err(err) => {
+                    failure = Some(err);
+                    break;
+                }
+            }
+        }
+        // the files moved so far are saved in any case, so that the library matches the disk
+        lib.relink(&moved);
+        lib.save()?;
+        match failure {
+            Some(err) => bail!(
+                "stopped after moving {} of {} files: {:#}",
+                moved.len(),
+                total,
+                err
+            ),
+            None => {
+                println!("Moved {} files.", moved.len());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Where the files of `papers` belong after `template` below `root`. The file a paper opens by
+/// default gets the name of the template, all other files the name followed by their role, e.g.
+/// `lindermayr2022learning-slides.pdf`. Existing files are never overwritten, so if another
+/// file is in the way, a number is appended.
+fn plan(papers: &[&LocalPaper], template: &Template, root: &Path) -> Plan {
+    let mut plan = Plan::default();
+    let mut planned: HashSet<PathBuf> = HashSet::new();
+    for paper in papers {
+        let primary = paper.location();
+        for attachment in &paper.attachments {
+            if !attachment.exists() {
+                plan.missing.push(attachment.path.clone());
+                continue;
+            }
+            let extension = attachment
+                .path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| "pdf".to_owned());
+            let mut target = root.join(template.file_path(paper, &extension));
+            if primary != Some(attachment.path.as_path()) {
+                let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+                let name = format!("{}-{}.{}", stem, attachment.role, extension);
+                target.set_file_name(name);
+            }
+
+            // `root` is canonical, so the paths are compared as such
+            let from = attachment
+                .path
+                .canonicalize()
+                .unwrap_or_else(|_| attachment.path.clone());
+            let is_from = |path: &Path| path.canonicalize().is_ok_and(|path| path == from);
+            let to = unique_path(&target, |path| {
+                planned.contains(path) || (path.exists() && !is_from(path))
+            });
+            if is_from(&to) {
+                plan.in_place += 1;
+                continue;
+            }
+            planned.insert(to.clone());
+            plan.moves.push(Move {
+                info: paper.metadata().clone(),
+                from: attachment.path.clone(),
+                to,
+            });
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        library::{Attachment, AttachmentRole},
+        PaperTitle, Venue,
+    };
+
+    fn paper(dir: &Path, files: &[(&str, AttachmentRole)]) -> LocalPaper {
+        let info = PaperInfo {
+            id: None,
+            title: PaperTitle::new("Learning-Augmented Online Algorithms".to_owned()),
+            venue: Venue::Conf("SODA".to_owned()),
+            authors: vec!["Alexander Lindermayr".to_owned()],
+            year: "2022".to_owned(),
+            summary: None,
+        };
+        let attachments = files
+            .iter()
+            .map(|(name, role)| {
+                let path = dir.join(name);
+                fs::write(&path, name).unwrap();
+                Attachment::new(path, *role)
+            })
+            .collect();
+        LocalPaper::new(info, attachments, vec![])
+    }
+
+    fn targets(plan: &Plan) -> Vec<String> {
+        plan.moves
+            .iter()
+            .map(|m| m.to.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn preprint_only_gets_the_plain_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let paper = paper(
+            &root,
+            &[("2201.00001v2.pdf", AttachmentRole::Arxiv(Some(2)))],
+        );
+        let template: Template = "{citekey}".parse().unwrap();
+        let plan = plan(&[&paper], &template, &root);
+        assert_eq!(targets(&plan), ["lindermayr2022learning.pdf"]);
+    }
+
+    #[test]
+    fn other_files_get_their_role() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let paper = paper(
+            &root,
+            &[
+                ("old.pdf", AttachmentRole::Arxiv(Some(1))),
+                ("published.pdf", AttachmentRole::Main),
+                ("talk.pdf", AttachmentRole::Slides),
+            ],
+        );
+        let template: Template = "{citekey}".parse().unwrap();
+        let plan = plan(&[&paper], &template, &root);
+        assert_eq!(
+            targets(&plan),
+            [
+                "lindermayr2022learning-arxiv-v1.pdf",
+                "lindermayr2022learning.pdf",
+                "lindermayr2022learning-slides.pdf"
+            ]
+        );
+    }
+}
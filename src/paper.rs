@@ -5,7 +5,6 @@ use std::{
 
 use crate::{
     library::LocalPaper,
-    query::QueryTerm,
     remotes::{arxiv::ArxivPaper, dblp::DBLPPaper},
 };
 
@@ -28,8 +27,8 @@ pub fn merge_papers<I: Iterator<Item = PaperHit>>(hits: I) -> Result<Vec<Paper>>
     let mut papers: Vec<Paper> = hits
         .map(|p| (p.metadata().title.normalized(), p))
         .into_group_map()
-        .into_iter()
-        .map(|(_, v)| Paper::new(v))
+        .into_values()
+        .map(Paper::new)
         .collect();
 
     papers.sort_by_key(|r| r.metadata().year.to_owned());
@@ -44,8 +43,8 @@ pub fn merge_to_papers<I: Iterator<Item = PaperHit>>(papers: &mut Vec<Paper>, hi
         .chain(hits)
         .map(|p| (p.metadata().title.normalized(), p))
         .into_group_map()
-        .into_iter()
-        .map(|(_, mut v)| {
+        .into_values()
+        .map(|mut v| {
             v.sort_by(|a, b| match (a, b) {
                 (PaperHit::Local(_), PaperHit::Local(_)) => Ordering::Equal,
                 (PaperHit::Arxiv(_), PaperHit::Arxiv(_)) => Ordering::Equal,
@@ -160,17 +159,18 @@ impl Display for Venue {
 }
 
 impl PaperInfo {
+    /// Whether the text terms of `query` match this paper. Filters are checked by `LocalPaper::matches`.
     pub fn matches(&self, query: &Query) -> bool {
-        let single_string = self.single_string();
-        let terms: Vec<String> = single_string
+        query.matches_tokens(&self.tokens().collect::<Vec<String>>())
+    }
+
+    /// Lowercase words of title, authors, venue and year, as used for searching.
+    pub fn tokens(&self) -> impl Iterator<Item = String> {
+        self.single_string()
             .split_whitespace()
             .map(|t| t.trim().to_lowercase())
-            .collect();
-
-        query.into_iter().all(|term| match term {
-            QueryTerm::Exact(q) => terms.iter().any(|t| q == t),
-            QueryTerm::Prefix(q) => terms.iter().any(|t| t.starts_with(q)),
-        })
+            .collect::<Vec<String>>()
+            .into_iter()
     }
 
     fn single_string(&self) -> String {
@@ -183,41 +183,6 @@ impl PaperInfo {
         )
         .to_lowercase()
     }
-
-    pub fn default_filename(&self) -> String {
-        let first_author = self
-            .authors
-            .first()
-            .unwrap()
-            .split_whitespace()
-            .last()
-            .unwrap()
-            .to_lowercase();
-        let other_names = self
-            .authors
-            .iter()
-            .skip(1)
-            .flat_map(|a| a.chars().next().map(|c| c.to_ascii_uppercase()))
-            .join("");
-        let title = self
-            .title
-            .words
-            .clone()
-            .into_iter()
-            .take(2)
-            .map(|w| w.to_uppercase())
-            .collect::<Vec<String>>()
-            .join("")
-            .to_lowercase();
-        format!(
-            "{}{}{}{}",
-            first_author,
-            other_names,
-            self.year[2..].to_owned(),
-            title
-        )
-        .replace('/', "-")
-    }
 }
 
 impl std::fmt::Display for PaperInfo {
@@ -250,8 +215,7 @@ pub struct PaperTitle {
 impl PaperTitle {
     pub fn new(title: String) -> Self {
         let words = title
-            .replace('.', "")
-            .replace('$', "")
+            .replace(['.', '$'], "")
             .split_whitespace()
             .map(|s| s.to_string())
             .collect();
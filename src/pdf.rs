@@ -0,0 +1,194 @@
+//! Metadata stored in the document information dictionary of PDF files, and their text.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{Context, Result};
+use lopdf::{content::Content, Document, Object, ObjectId};
+
+/// Number of pages whose text is extracted, which is where identifiers and the title are.
+const TEXT_PAGES: u32 = 2;
+
+/// Title and authors of a PDF file, as far as they are given in the file.
+#[derive(Debug, Default, Clone)]
+pub struct PdfInfo {
+    pub title: Option<String>,
+    pub authors: Vec<String>,
+    /// Text of the first pages, empty if it cannot be extracted.
+    pub text: Vec<TextLine>,
+}
+
+/// A line of text with the largest font size used in it.
+#[derive(Debug, Clone)]
+pub struct TextLine {
+    pub text: String,
+    pub size: f32,
+    pub page: u32,
+}
+
+pub fn read_info(path: &Path) -> Result<PdfInfo> {
+    let doc =
+        Document::load(path).with_context(|| format!("could not read PDF: {}", path.display()))?;
+    Ok(PdfInfo {
+        title: get_title(&doc),
+        authors: get_author(&doc)
+            .map(|authors| split_authors(&authors))
+            .unwrap_or_default(),
+        text: doc
+            .get_pages()
+            .into_iter()
+            .take(TEXT_PAGES as usize)
+            .flat_map(|(page, id)| {
+                page_lines(&doc, id)
+                    .unwrap_or_else(|err| {
+                        log::warn!("Could not extract text of {}: {}", path.display(), err);
+                        vec![]
+                    })
+                    .into_iter()
+                    .map(move |(text, size)| TextLine { text, size, page })
+            })
+            .collect(),
+    })
+}
+
+/// The lines of text on a page in the order they are drawn, which is the reading order for
+/// most documents. Unlike `Document::extract_text`, this keeps the font sizes and the spaces
+/// which are only given as gaps between the words.
+fn page_lines(doc: &Document, page: ObjectId) -> Result<Vec<(String, f32)>> {
+    let encodings: BTreeMap<Vec<u8>, &str> = doc
+        .get_page_fonts(page)
+        .into_iter()
+        .map(|(name, font)| (name, font.get_font_encoding()))
+        .collect();
+    let content = Content::decode(&doc.get_page_content(page)?)?;
+
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut line_size: f32 = 0.0;
+    let mut encoding = None;
+    let (mut font_size, mut scale) = (0.0, 1.0);
+    let mut flush = |line: &mut String, line_size: &mut f32| {
+        let text = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !text.is_empty() {
+            lines.push((text, *line_size));
+        }
+        line.clear();
+        *line_size = 0.0;
+    };
+    for op in &content.operations {
+        let number = |i: usize| op.operands.get(i).and_then(|o| o.as_float().ok());
+        match op.operator.as_str() {
+            "Tf" => {
+                encoding = op
+                    .operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .and_then(|name| encodings.get(name))
+                    .copied();
+                font_size = number(1).unwrap_or(0.0);
+            }
+            "Tm" => {
+                flush(&mut line, &mut line_size);
+                scale = number(2)
+                    .zip(number(3))
+                    .map(|(c, d)| c.hypot(d))
+                    .unwrap_or(1.0);
+            }
+            "Td" | "TD" => match number(1) {
+                Some(y) if y != 0.0 => flush(&mut line, &mut line_size),
+                _ => line.push(' '),
+            },
+            "T*" | "ET" => flush(&mut line, &mut line_size),
+            _ => {}
+        }
+        if !matches!(op.operator.as_str(), "Tj" | "TJ" | "'" | "\"") {
+            continue;
+        }
+        if op.operator != "TJ" && op.operator != "Tj" {
+            flush(&mut line, &mut line_size);
+        }
+        // Type0 fonts need their CMaps, which lopdf does not decode
+        if encoding == Some("Identity-H") {
+            continue;
+        }
+        for operand in &op.operands {
+            let parts = match operand {
+                Object::Array(parts) => parts.as_slice(),
+                operand => std::slice::from_ref(operand),
+            };
+            for part in parts {
+                match part {
+                    Object::String(bytes, _) => {
+                        line.push_str(&Document::decode_text(encoding, bytes));
+                        line_size = line_size.max(font_size * scale);
+                    }
+                    // a gap of more than a fifth of the font size separates words
+                    part => {
+                        if part.as_float().is_ok_and(|gap| gap < -200.0) {
+                            line.push(' ');
+                        }
+                    }
+                }
+            }
+        }
+    }
+    flush(&mut line, &mut line_size);
+    Ok(lines)
+}
+
+/// The title, unless it is obviously the name of the file the PDF was created from.
+pub fn get_title(doc: &Document) -> Option<String> {
+    get_info_field(doc, "Title").filter(|title| {
+        let lower = title.to_lowercase();
+        ![".pdf", ".dvi", ".tex", ".doc", ".docx", ".ps"]
+            .iter()
+            .any(|ext| lower.ends_with(ext))
+            && !lower.starts_with("microsoft word")
+            && !lower.starts_with("untitled")
+    })
+}
+
+pub fn get_author(doc: &Document) -> Option<String> {
+    get_info_field(doc, "Author")
+}
+
+fn get_info_field(doc: &Document, field_name: &str) -> Option<String> {
+    let info = doc.trailer.get(b"Info").ok()?;
+    let dict = match *info {
+        Object::Dictionary(ref dict) => Some(dict),
+        Object::Reference(ref id) => doc.objects.get(id).and_then(|o| o.as_dict().ok()),
+        _ => None,
+    }?;
+    let field = decode_text(dict.get(field_name.as_bytes()).ok()?.as_str().ok()?);
+    let field = field.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if field.is_empty() {
+        None
+    } else {
+        Some(field)
+    }
+}
+
+/// Decodes a PDF text string, which is either UTF-16BE with a byte order mark or
+/// PDFDocEncoding, approximated by Latin-1.
+fn decode_text(bytes: &[u8]) -> String {
+    match bytes {
+        [0xfe, 0xff, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Splits an author field like `A. Lindermayr, N. Megow` or `Lindermayr; Megow` into names.
+fn split_authors(authors: &str) -> Vec<String> {
+    let separator = if authors.contains(';') { ";" } else { "," };
+    authors
+        .split(separator)
+        .flat_map(|a| a.split(" and "))
+        .map(|a| a.trim().to_owned())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
@@ -7,6 +7,37 @@ pub struct Query {
 pub enum QueryTerm {
     Prefix(String),
     Exact(String),
+    Filter(Filter),
+}
+
+/// Terms of the form `key:value` which only apply to papers in the local library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Tag(String),
+    Collection(String),
+    /// Reading status, e.g. `status:unread`.
+    Status(String),
+    /// Priority in the reading queue, e.g. `priority:high`.
+    Priority(String),
+    /// Minimum number of stars, e.g. `rating:4`.
+    Rating(String),
+}
+
+impl Filter {
+    fn parse(term: &str) -> Option<Filter> {
+        let (key, value) = term.split_once(':')?;
+        if value.is_empty() {
+            return None;
+        }
+        match key {
+            "tag" => Some(Filter::Tag(value.to_owned())),
+            "collection" => Some(Filter::Collection(value.to_owned())),
+            "status" => Some(Filter::Status(value.to_owned())),
+            "priority" => Some(Filter::Priority(value.to_owned())),
+            "rating" => Some(Filter::Rating(value.to_owned())),
+            _ => None,
+        }
+    }
 }
 
 impl Query {
@@ -17,6 +48,29 @@ impl Query {
     pub fn is_empty(&self) -> bool {
         self.terms.is_empty()
     }
+
+    /// All terms which are matched against the text of a paper.
+    pub fn text_terms(&self) -> impl Iterator<Item = &QueryTerm> {
+        self.terms
+            .iter()
+            .filter(|t| !matches!(t, QueryTerm::Filter(_)))
+    }
+
+    /// Whether every text term matches one of the lowercase `tokens`.
+    pub fn matches_tokens(&self, tokens: &[String]) -> bool {
+        self.text_terms().all(|term| match term {
+            QueryTerm::Exact(q) => tokens.iter().any(|t| q == t),
+            QueryTerm::Prefix(q) => tokens.iter().any(|t| t.starts_with(q)),
+            QueryTerm::Filter(_) => true,
+        })
+    }
+
+    pub fn filters(&self) -> impl Iterator<Item = &Filter> {
+        self.terms.iter().filter_map(|t| match t {
+            QueryTerm::Filter(f) => Some(f),
+            _ => None,
+        })
+    }
 }
 
 impl IntoIterator for Query {
@@ -43,7 +97,9 @@ impl From<String> for Query {
             .split_whitespace()
             .map(|t| {
                 let prep = t.trim().to_lowercase();
-                if prep.ends_with('$') {
+                if let Some(filter) = Filter::parse(&prep) {
+                    QueryTerm::Filter(filter)
+                } else if prep.ends_with('$') {
                     let mut chars = prep.chars();
                     chars.next_back();
                     QueryTerm::Exact(chars.as_str().to_string())
@@ -28,6 +28,13 @@ impl ArxivPaper {
         ))
     }
 
+    /// The latest version of the preprint, as given by the version suffix of its URL.
+    pub fn version(&self) -> Option<u32> {
+        let url = self.ee.raw();
+        let (_, version) = url.rsplit_once('v')?;
+        version.parse().ok()
+    }
+
     pub fn remote_tag(&self) -> String {
         style(format!("arXiv({})", self.metadata().year))
             .yellow()
@@ -45,16 +52,28 @@ impl Display for ArxivPaper {
 #[derive(Clone, Debug)]
 pub struct Arxiv;
 
+impl Arxiv {
+    /// URL of the entries of the given preprints, to be parsed by `parse_response`.
+    pub fn id_list_url(ids: &[ArxivIdentifier]) -> String {
+        format!(
+            "http://export.arxiv.org/api/query?id_list={}&max_results={}",
+            ids.iter().join(","),
+            ids.len()
+        )
+    }
+}
+
 impl OnlineRemote for Arxiv {
     fn get_url(query: &Query, max_hits: usize) -> String {
         format!(
             "http://export.arxiv.org/api/query?search_query={}&max_results={}",
             query
                 .into_iter()
-                .map(|t| {
+                .filter_map(|t| {
                     match t {
-                        QueryTerm::Exact(q) => q.to_string(),
-                        QueryTerm::Prefix(q) => q.to_string(),
+                        QueryTerm::Exact(q) => Some(q.to_string()),
+                        QueryTerm::Prefix(q) => Some(q.to_string()),
+                        QueryTerm::Filter(_) => None,
                     }
                 })
                 .join("+AND+"),
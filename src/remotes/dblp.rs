@@ -57,10 +57,11 @@ impl OnlineRemote for Dblp {
             "https://dblp.org/search/publ/api?q={}&h={}",
             query
                 .into_iter()
-                .map(|t| {
+                .filter_map(|t| {
                     match t {
-                        QueryTerm::Exact(q) => format!("{}$", q),
-                        QueryTerm::Prefix(q) => q.to_string(),
+                        QueryTerm::Exact(q) => Some(format!("{}$", q)),
+                        QueryTerm::Prefix(q) => Some(q.to_string()),
+                        QueryTerm::Filter(_) => None,
                     }
                 })
                 .join("+"),
@@ -34,6 +34,13 @@ where
     R: OnlineRemote + std::marker::Send + std::marker::Sync,
 {
     async fn fetch_from_remote(&self, query: Query, max_hits: usize) -> Result<FetchResult> {
+        if query.text_terms().next().is_none() {
+            // filters only apply to the local library
+            return Ok(FetchResult {
+                query,
+                hits: vec![],
+            });
+        }
         let response = reqwest::get(Self::get_url(&query, max_hits))
             .await
             .map_err(|err| anyhow!(err))?;
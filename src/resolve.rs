@@ -0,0 +1,210 @@
+//! Finds the metadata of a local file by its identifier, or by searching DBLP and arXiv.
+
+use std::{
+    collections::HashSet,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use deunicode::deunicode;
+use reqwest::header::{ACCEPT, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    library::{Attachment, AttachmentRole, LocalPaper},
+    remotes::{arxiv::Arxiv, dblp::Dblp, OnlineRemote, Remote},
+    template::surname,
+    ArxivIdentifier, Doi, Identifier, PaperHit, PaperInfo, PaperUrl, Query,
+};
+
+/// Score from which a candidate is taken without asking.
+pub const CONFIDENT: f64 = 0.9;
+
+/// A paper found online for a local file, with a score from 0 to 1 of how well it matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    pub paper: LocalPaper,
+    pub score: f64,
+}
+
+/// Searches DBLP and arXiv for the paper with `title` and `authors` and returns the candidates
+/// best first, each with `path` attached. Candidates from DBLP come first among equal scores.
+/// Fails only if no remote could be reached.
+pub async fn search(title: &str, authors: &[String], path: &Path) -> Result<Vec<Candidate>> {
+    let words = words(title);
+    if words.is_empty() {
+        return Ok(vec![]);
+    }
+    let query = Query::from(words.join(" "));
+    let (dblp, arxiv) = tokio::join!(
+        Dblp.fetch_from_remote(query.clone(), 10),
+        Arxiv.fetch_from_remote(query, 10)
+    );
+
+    if let (Err(err), Err(_)) = (&dblp, &arxiv) {
+        bail!("could not search online: {}", err);
+    }
+    let mut candidates: Vec<Candidate> = vec![];
+    for result in [dblp, arxiv] {
+        match result {
+            Ok(result) => candidates.extend(result.hits.into_iter().filter_map(|hit| {
+                let score = score(hit.metadata(), title, authors);
+                Some(Candidate {
+                    paper: to_local(hit, path)?,
+                    score,
+                })
+            })),
+            Err(err) => log::warn!("Could not search for {}: {}", title, err),
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(candidates)
+}
+
+/// How well `info` matches the `title` and `authors` of a file: the overlap of the title words,
+/// and if authors are given, of the author surnames.
+pub fn score(info: &PaperInfo, title: &str, authors: &[String]) -> f64 {
+    let ours = title_words(title);
+    let theirs = title_words(&info.title.to_string());
+    let union = ours.union(&theirs).count();
+    let title_score = if union == 0 {
+        0.0
+    } else {
+        ours.intersection(&theirs).count() as f64 / union as f64
+    };
+    if authors.is_empty() || info.authors.is_empty() {
+        return title_score;
+    }
+
+    let ours = deunicode(&authors.join(" ")).to_lowercase();
+    let found = info
+        .authors
+        .iter()
+        .filter_map(|a| surname(a))
+        .map(|surname| deunicode(surname).to_lowercase())
+        .filter(|surname| ours.contains(surname.as_str()))
+        .count();
+    let author_score = found as f64 / info.authors.len() as f64;
+    0.75 * title_score + 0.25 * author_score
+}
+
+/// Looks up the paper with `id` on arXiv or doi.org, with `path` attached. Returns `None` if
+/// the paper is unknown or `id` is a custom identifier.
+pub async fn lookup(id: &Identifier, path: &Path) -> Result<Option<LocalPaper>> {
+    match id {
+        Identifier::Arxiv(arxiv) => Ok(lookup_arxiv(std::slice::from_ref(arxiv))
+            .await?
+            .into_iter()
+            .find(|hit| hit.metadata().id.as_ref() == Some(id))
+            .and_then(|hit| to_local(hit, path))),
+        Identifier::Doi(doi) => Ok(lookup_doi(doi)
+            .await?
+            .map(|info| doi_to_local(info, doi, path))),
+        Identifier::Custom(_) => Ok(None),
+    }
+}
+
+/// Looks up the arXiv preprints with the given identifiers in a single request.
+pub async fn lookup_arxiv(ids: &[ArxivIdentifier]) -> Result<Vec<PaperHit>> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let response = reqwest::get(Arxiv::id_list_url(ids))
+        .await
+        .map_err(|err| anyhow!(err))?;
+    let body = response.text().await.map_err(|err| anyhow!(err))?;
+    Arxiv::parse_response(&body)
+}
+
+/// Looks up the metadata of `doi` at doi.org. Returns `None` if the DOI is unknown.
+pub async fn lookup_doi(doi: &Doi) -> Result<Option<PaperInfo>> {
+    let response = reqwest::Client::new()
+        .get(format!("https://doi.org/{}", doi))
+        .header(ACCEPT, "application/vnd.citationstyles.csl+json")
+        .header(USER_AGENT, "xivar")
+        .send()
+        .await
+        .map_err(|err| anyhow!(err))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let csl: Value = response
+        .error_for_status()
+        .map_err(|err| anyhow!(err))?
+        .json()
+        .await
+        .map_err(|err| anyhow!(err))?;
+    Ok(crate::formats::csl::to_info(&csl).map(|info| PaperInfo {
+        id: Some(Identifier::Doi(doi.clone())),
+        ..info
+    }))
+}
+
+/// The paper with `info` found by its DOI, with `path` attached.
+pub fn doi_to_local(info: PaperInfo, doi: &Doi, path: &Path) -> LocalPaper {
+    LocalPaper::new(
+        info,
+        vec![Attachment::new(path, AttachmentRole::Main)],
+        vec![PaperUrl::new(format!("https://doi.org/{}", doi))],
+    )
+}
+
+/// Spaces out requests to a remote by a minimum interval.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        RateLimiter {
+            interval,
+            last: None,
+        }
+    }
+
+    /// Waits until the interval since the previous request has passed.
+    pub async fn wait(&mut self) {
+        if let Some(last) = self.last {
+            tokio::time::sleep_until((last + self.interval).into()).await;
+        }
+        self.last = Some(Instant::now());
+    }
+}
+
+/// The paper of a remote search hit, with `path` attached.
+pub fn to_local(hit: PaperHit, path: &Path) -> Option<LocalPaper> {
+    match hit {
+        PaperHit::Dblp(paper) => Some(LocalPaper::new(
+            paper.metadata().clone(),
+            vec![Attachment::new(path, AttachmentRole::Main)],
+            vec![paper.ee],
+        )),
+        PaperHit::Arxiv(paper) => Some(LocalPaper::new(
+            paper.metadata().clone(),
+            vec![Attachment::new(
+                path,
+                AttachmentRole::Arxiv(paper.version()),
+            )],
+            vec![paper.ee],
+        )),
+        PaperHit::Local(_) => None,
+    }
+}
+
+/// Lowercase ASCII words of a title, in order.
+fn words(title: &str) -> Vec<String> {
+    deunicode(title)
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_owned())
+        .collect()
+}
+
+fn title_words(title: &str) -> HashSet<String> {
+    words(title).into_iter().collect()
+}
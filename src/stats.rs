@@ -0,0 +1,228 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
+
+use anyhow::Result;
+use clap::Parser;
+use console::style;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    library::{Library, LocalPaper},
+    xiv_config::Config,
+    Identifier, Venue,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Show statistics of your library")]
+pub struct Stats {
+    /// Print the statistics as JSON
+    #[clap(long)]
+    json: bool,
+
+    /// Number of top authors and co-author pairs to show
+    #[clap(short, long, default_value = "10")]
+    top: usize,
+}
+
+impl Stats {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let lib = Library::open(&config)?;
+        let stats = LibraryStats::of(lib.iter(), self.top);
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+        } else {
+            stats.print();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct LibraryStats {
+    papers: usize,
+    by_year: BTreeMap<String, usize>,
+    by_venue: VenueCounts,
+    top_authors: Vec<AuthorCount>,
+    top_coauthors: Vec<CoauthorCount>,
+    identifiers: IdentifierCounts,
+    files: FileStats,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct VenueCounts {
+    journal: usize,
+    conference: usize,
+    arxiv: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct AuthorCount {
+    author: String,
+    papers: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct CoauthorCount {
+    authors: [String; 2],
+    papers: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct IdentifierCounts {
+    doi: usize,
+    arxiv: usize,
+    custom: usize,
+    none: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct FileStats {
+    attachments: usize,
+    missing: usize,
+    /// Papers without any existing file.
+    papers_without_files: usize,
+    /// Total size in bytes of all existing files, each file counted once.
+    disk_usage: u64,
+}
+
+impl LibraryStats {
+    fn of<'a>(papers: impl Iterator<Item = &'a LocalPaper>, top: usize) -> Self {
+        let mut stats = LibraryStats::default();
+        let mut authors: HashMap<&str, usize> = HashMap::new();
+        let mut pairs: HashMap<(&str, &str), usize> = HashMap::new();
+        let mut seen_files: HashSet<&Path> = HashSet::new();
+
+        for paper in papers {
+            let info = paper.metadata();
+            stats.papers += 1;
+            *stats.by_year.entry(info.year.clone()).or_default() += 1;
+            match info.venue {
+                Venue::Journal(_) => stats.by_venue.journal += 1,
+                Venue::Conf(_) => stats.by_venue.conference += 1,
+                Venue::Arxiv(_) => stats.by_venue.arxiv += 1,
+            }
+            match info.id {
+                Some(Identifier::Doi(_)) => stats.identifiers.doi += 1,
+                Some(Identifier::Arxiv(_)) => stats.identifiers.arxiv += 1,
+                Some(Identifier::Custom(_)) => stats.identifiers.custom += 1,
+                None => stats.identifiers.none += 1,
+            }
+
+            let names: Vec<&str> = info
+                .authors
+                .iter()
+                .map(|a| a.trim())
+                .filter(|a| !a.is_empty())
+                .sorted()
+                .dedup()
+                .collect();
+            for name in &names {
+                *authors.entry(name).or_default() += 1;
+            }
+            for (a, b) in names.iter().tuple_combinations() {
+                *pairs.entry((a, b)).or_default() += 1;
+            }
+
+            let mut has_file = false;
+            for attachment in &paper.attachments {
+                stats.files.attachments += 1;
+                match std::fs::metadata(&attachment.path) {
+                    Ok(meta) => {
+                        has_file = true;
+                        if seen_files.insert(&attachment.path) {
+                            stats.files.disk_usage += meta.len();
+                        }
+                    }
+                    Err(_) => stats.files.missing += 1,
+                }
+            }
+            if !has_file {
+                stats.files.papers_without_files += 1;
+            }
+        }
+
+        stats.top_authors = authors
+            .into_iter()
+            .sorted_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)))
+            .take(top)
+            .map(|(author, papers)| AuthorCount {
+                author: author.to_owned(),
+                papers,
+            })
+            .collect();
+        stats.top_coauthors = pairs
+            .into_iter()
+            .filter(|(_, papers)| *papers > 1)
+            .sorted_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)))
+            .take(top)
+            .map(|((a, b), papers)| CoauthorCount {
+                authors: [a.to_owned(), b.to_owned()],
+                papers,
+            })
+            .collect();
+        stats
+    }
+
+    fn print(&self) {
+        println!("{} {}", style("Papers").bold(), self.papers);
+
+        section("Year");
+        for (year, count) in self.by_year.iter().rev() {
+            let year = if year.is_empty() { "?" } else { year };
+            row(year, *count);
+        }
+
+        section("Venue");
+        row("journal", self.by_venue.journal);
+        row("conference", self.by_venue.conference);
+        row("arxiv", self.by_venue.arxiv);
+
+        section("Identifier");
+        row("doi", self.identifiers.doi);
+        row("arxiv", self.identifiers.arxiv);
+        row("custom", self.identifiers.custom);
+        row("none", self.identifiers.none);
+
+        section("Top authors");
+        for author in &self.top_authors {
+            row(&author.author, author.papers);
+        }
+
+        section("Top co-authors");
+        for pair in &self.top_coauthors {
+            row(&pair.authors.join(" & "), pair.papers);
+        }
+
+        section("Files");
+        row("attachments", self.files.attachments);
+        row("missing", self.files.missing);
+        row("papers without files", self.files.papers_without_files);
+        println!("  {:<40} {:>6}", "disk usage", format_size(self.files.disk_usage));
+    }
+}
+
+fn section(title: &str) {
+    println!("\n{}", style(title).bold());
+}
+
+fn row(label: &str, count: usize) {
+    println!("  {:<40} {:>6}", label, count);
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use console::style;
+
+use crate::{
+    library::{Library, LocalPaper},
+    xiv_config::Config,
+    Query,
+};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Tag papers of your library and group them into collections")]
+pub struct Tag {
+    /// Papers to change, lists all tags and collections if omitted
+    query: Vec<String>,
+
+    /// Add a tag
+    #[clap(short, long = "add", value_name = "TAG")]
+    add: Vec<String>,
+
+    /// Remove a tag
+    #[clap(short, long = "remove", value_name = "TAG")]
+    remove: Vec<String>,
+
+    /// Add to a collection
+    #[clap(short = 'c', long = "collect", value_name = "COLLECTION")]
+    collect: Vec<String>,
+
+    /// Remove from a collection
+    #[clap(short = 'u', long = "uncollect", value_name = "COLLECTION")]
+    uncollect: Vec<String>,
+
+    /// Change all matching papers instead of requiring a unique match
+    #[clap(long)]
+    all: bool,
+}
+
+impl Tag {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let mut lib = Library::open(&config)?;
+
+        if self.query.is_empty() {
+            print_overview(&lib);
+            return Ok(());
+        }
+
+        let query = Query::from(self.query.join(" "));
+        let matches: Vec<LocalPaper> = lib.iter_matches(&query).cloned().collect();
+
+        if matches.is_empty() {
+            bail!("No paper in your library matches the query!");
+        }
+
+        let changes = !(self.add.is_empty()
+            && self.remove.is_empty()
+            && self.collect.is_empty()
+            && self.uncollect.is_empty());
+
+        if changes && matches.len() > 1 && !self.all {
+            for paper in &matches {
+                println!("{}", paper);
+            }
+            bail!(
+                "The query matches {} papers. Refine it or pass --all to change all of them.",
+                matches.len()
+            );
+        }
+
+        for mut paper in matches {
+            if changes {
+                self.apply(&mut paper);
+                lib.update(paper.clone());
+            }
+            println!(
+                "{}\n    tags: {}\n    collections: {}",
+                paper.metadata(),
+                paper.tags.join(", "),
+                paper.collections.join(", ")
+            );
+        }
+
+        lib.save()
+    }
+
+    fn apply(&self, paper: &mut LocalPaper) {
+        for tag in &self.add {
+            paper.add_tag(tag);
+        }
+        for tag in &self.remove {
+            paper.remove_tag(tag);
+        }
+        for name in &self.collect {
+            paper.add_to_collection(name);
+        }
+        for name in &self.uncollect {
+            paper.remove_from_collection(name);
+        }
+    }
+}
+
+fn print_overview(lib: &Library) {
+    let mut tags: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut collections: BTreeMap<&str, usize> = BTreeMap::new();
+    for paper in lib.iter() {
+        for tag in &paper.tags {
+            *tags.entry(tag).or_default() += 1;
+        }
+        for name in &paper.collections {
+            *collections.entry(name).or_default() += 1;
+        }
+    }
+    println!("{}", style("Tags").bold());
+    for (tag, count) in tags {
+        println!("    {} ({})", tag, count);
+    }
+    println!("{}", style("Collections").bold());
+    for (name, count) in collections {
+        println!("    {} ({})", name, count);
+    }
+}
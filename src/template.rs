@@ -0,0 +1,381 @@
+//! Templates like `{author}{year}{veryshorttitle}`, which are filled in with the metadata of a
+//! paper, e.g. to get its citation key or the name of its file.
+
+use std::{
+    fmt::Display,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Error, Result};
+use deunicode::deunicode;
+
+use crate::{library::LocalPaper, PaperTitle};
+
+/// Words skipped in short titles.
+const STOP_WORDS: [&str; 12] = [
+    "a", "an", "the", "on", "of", "in", "for", "and", "to", "with", "from", "towards",
+];
+
+/// Number of authors and title words in `{authors}` and `{shorttitle}` without a count.
+const SHORT: usize = 3;
+
+/// Maximal length of a field in a file name, so that long titles fit.
+const MAX_FILE_FIELD: usize = 80;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    /// A field with an optional count, e.g. `{shorttitle:2}`.
+    Field(Field, Option<usize>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    /// Surname of the first author.
+    Author,
+    /// Surnames of the first three (or `count`) authors, or of the first author and `etal` if
+    /// there are more.
+    Authors,
+    Year,
+    Title,
+    /// The first three (or `count`) words of the title which are no stop words.
+    ShortTitle,
+    /// The first word of the title which is no stop word.
+    VeryShortTitle,
+    Venue,
+    Citekey,
+    /// The first tag of the paper.
+    Tag,
+    /// Surname of the first author, initials of the others, the last two digits of the year and
+    /// the first two words of the title, e.g. `lindermayrNB22doublecoverage`, as downloads were
+    /// named before the name could be configured.
+    Compact,
+}
+
+const FIELDS: [(&str, Field); 10] = [
+    ("author", Field::Author),
+    ("authors", Field::Authors),
+    ("year", Field::Year),
+    ("title", Field::Title),
+    ("shorttitle", Field::ShortTitle),
+    ("veryshorttitle", Field::VeryShortTitle),
+    ("venue", Field::Venue),
+    ("citekey", Field::Citekey),
+    ("tag", Field::Tag),
+    ("compact", Field::Compact),
+];
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let mut parts = vec![];
+        let mut rest = source;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_owned()));
+            }
+            let end = match rest[start..].find('}') {
+                Some(end) => end,
+                None => bail!("unclosed `{{` in template {}", source),
+            };
+            let (name, count) = match rest[start + 1..start + end].split_once(':') {
+                Some((name, count)) => match count.parse::<usize>() {
+                    Ok(count) if count > 0 => (name, Some(count)),
+                    _ => bail!(
+                        "invalid count {} of {{{}}} in template {}",
+                        count,
+                        name,
+                        source
+                    ),
+                },
+                None => (&rest[start + 1..start + end], None),
+            };
+            match FIELDS.iter().find(|(n, _)| *n == name) {
+                Some((_, field))
+                    if count.is_some() && !matches!(field, Field::Authors | Field::ShortTitle) =>
+                {
+                    bail!("{{{}}} in template {} takes no count", name, source)
+                }
+                Some((_, field)) => parts.push(Part::Field(*field, count)),
+                None => bail!(
+                    "unknown field {{{}}} in template {}, expected one of {}",
+                    name,
+                    source,
+                    FIELDS
+                        .iter()
+                        .map(|(n, _)| format!("{{{}}}", n))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_owned()));
+        }
+        Ok(Template {
+            source: source.to_owned(),
+            parts,
+        })
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Template {
+    /// Fills in the fields with the metadata of `paper`, passing every value through `escape`.
+    /// The text between the fields is kept as it is.
+    pub fn render(&self, paper: &LocalPaper, escape: impl Fn(&str) -> String) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field(field, count) => escape(&value(*field, *count, paper)),
+            })
+            .collect()
+    }
+
+    /// The path of a file of `paper` relative to the document directory, e.g.
+    /// `lindermayr2022learning.pdf`. The fields are transliterated to ASCII without characters
+    /// which are not allowed in file names, and `/` separates directories.
+    pub fn file_path(&self, paper: &LocalPaper, extension: &str) -> PathBuf {
+        let rendered = self.render(paper, |value| {
+            let value = deunicode(value).replace(['/', '\\'], "-");
+            let words: Vec<&str> = value.split_whitespace().collect();
+            file_name_chars(&words.join("_"))
+                .chars()
+                .take(MAX_FILE_FIELD)
+                .collect()
+        });
+        let mut path: PathBuf = Path::new(&rendered)
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(file_name_chars(&name.to_string_lossy())),
+                // never leave the document directory
+                _ => None,
+            })
+            .map(|name| name.trim_matches(['.', ' ']).to_owned())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if path.as_os_str().is_empty() {
+            path.push("paper");
+        }
+        let name = format!(
+            "{}.{}",
+            path.file_name().unwrap().to_string_lossy(),
+            extension
+        );
+        path.set_file_name(name);
+        path
+    }
+}
+
+/// `name` without the characters which are not allowed in file names on common file systems.
+fn file_name_chars(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_control() && !matches!(c, '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect()
+}
+
+fn value(field: Field, count: Option<usize>, paper: &LocalPaper) -> String {
+    let info = paper.metadata();
+    match field {
+        Field::Author => info
+            .authors
+            .first()
+            .and_then(|author| surname(author))
+            .unwrap_or_default()
+            .to_owned(),
+        Field::Authors => {
+            let surnames: Vec<&str> = info.authors.iter().filter_map(|a| surname(a)).collect();
+            if surnames.len() > count.unwrap_or(SHORT) {
+                format!("{} etal", surnames[0])
+            } else {
+                surnames.join(" ")
+            }
+        }
+        Field::Year => info.year.clone(),
+        Field::Title => info.title.to_string(),
+        Field::ShortTitle => significant_words(&info.title)
+            .take(count.unwrap_or(SHORT))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Field::VeryShortTitle => significant_words(&info.title)
+            .next()
+            .unwrap_or_default()
+            .to_owned(),
+        Field::Venue => info.venue.to_string(),
+        Field::Citekey => paper.citekey.clone(),
+        Field::Tag => paper.tags.first().cloned().unwrap_or_default(),
+        Field::Compact => {
+            let author = info
+                .authors
+                .first()
+                .and_then(|author| surname(author))
+                .unwrap_or_default()
+                .to_lowercase();
+            let initials: String = info
+                .authors
+                .iter()
+                .skip(1)
+                .filter_map(|author| author.chars().next())
+                .flat_map(char::to_uppercase)
+                .collect();
+            let year = info.year.get(info.year.len().saturating_sub(2)..);
+            let title: String = info
+                .title
+                .words
+                .iter()
+                .take(2)
+                .map(|w| w.to_lowercase())
+                .collect();
+            format!(
+                "{}{}{}{}",
+                author,
+                initials,
+                year.unwrap_or_default(),
+                title
+            )
+        }
+    }
+}
+
+/// The surname of `author`, i.e. the last name which is no number, as DBLP disambiguates
+/// homonymous authors by a number, e.g. `Alexander Lindermayr 0001`.
+pub fn surname(author: &str) -> Option<&str> {
+    author
+        .split_whitespace()
+        .rfind(|name| !name.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The words of `title` without punctuation and stop words.
+pub fn significant_words(title: &PaperTitle) -> impl Iterator<Item = &str> {
+    title
+        .words
+        .iter()
+        .flat_map(|w| w.split(|c: char| !c.is_alphanumeric()))
+        .filter(|w| {
+            let lower = deunicode(w).to_lowercase();
+            !lower.is_empty() && !STOP_WORDS.contains(&lower.as_str())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PaperInfo, Venue};
+
+    fn paper(title: &str, authors: &[&str], year: &str) -> LocalPaper {
+        let info = PaperInfo {
+            id: None,
+            title: PaperTitle::new(title.to_owned()),
+            venue: Venue::Conf("SODA".to_owned()),
+            authors: authors.iter().map(|a| a.to_string()).collect(),
+            year: year.to_owned(),
+            summary: None,
+        };
+        LocalPaper::new(info, vec![], vec![])
+    }
+
+    fn file_path(template: &str, paper: &LocalPaper) -> PathBuf {
+        template
+            .parse::<Template>()
+            .unwrap()
+            .file_path(paper, "pdf")
+    }
+
+    #[test]
+    fn compact_names() {
+        let advice = paper(
+            "Double Coverage with Machine-Learned Advice",
+            &["Alexander Lindermayr", "Nicole Megow", "Bertrand Simon"],
+            "2022",
+        );
+        assert_eq!(
+            file_path("{compact}", &advice),
+            Path::new("lindermayrNB22doublecoverage.pdf")
+        );
+        // without authors and year, which used to panic
+        let anonymous = paper("Scheduling", &[], "");
+        assert_eq!(
+            file_path("{compact}", &anonymous),
+            Path::new("scheduling.pdf")
+        );
+    }
+
+    #[test]
+    fn paths_stay_in_the_document_directory() {
+        let io = paper("Input/Output Scheduling", &["Nicole Megow"], "2022");
+        assert_eq!(
+            file_path("{title}", &io),
+            Path::new("Input-Output_Scheduling.pdf")
+        );
+        assert_eq!(
+            file_path("{year}/{author}", &io),
+            Path::new("2022/Megow.pdf")
+        );
+        assert_eq!(file_path("../../{author}", &io), Path::new("Megow.pdf"));
+        assert_eq!(file_path("/tmp/{author}", &io), Path::new("tmp/Megow.pdf"));
+
+        let dots = paper("..", &["Nicole Megow"], "2022");
+        assert_eq!(file_path("{title}/{author}", &dots), Path::new("Megow.pdf"));
+    }
+
+    #[test]
+    fn empty_fields() {
+        let untagged = paper("Scheduling", &[], "");
+        assert_eq!(
+            file_path("{tag}/{year}/{veryshorttitle}", &untagged),
+            Path::new("Scheduling.pdf")
+        );
+        assert_eq!(
+            file_path("{tag}{author}", &untagged),
+            Path::new("paper.pdf")
+        );
+    }
+
+    #[test]
+    fn non_ascii_names() {
+        let umlauts = paper(
+            "Über Fließbänder",
+            &["Jürgen Müller 0001", "Øystein Ore"],
+            "2020",
+        );
+        assert_eq!(
+            file_path("{authors}-{shorttitle}", &umlauts),
+            Path::new("Muller_Ore-Uber_Fliessbander.pdf")
+        );
+        assert_eq!(
+            "{author}{year}"
+                .parse::<Template>()
+                .unwrap()
+                .render(&umlauts, |v| v.to_owned()),
+            "Müller2020"
+        );
+    }
+
+    #[test]
+    fn invalid_templates() {
+        for source in [
+            "{unknown}",
+            "{author",
+            "{year:2}",
+            "{shorttitle:0}",
+            "{authors:x}",
+        ] {
+            assert!(source.parse::<Template>().is_err(), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{library::Library, xiv_config::Config};
+
+#[derive(Parser, Debug)]
+#[clap(about = "Undo the last changes to your library, see `xivar history`")]
+pub struct Undo {
+    /// Number of operations to undo
+    #[clap(default_value = "1")]
+    n: usize,
+}
+
+impl Undo {
+    pub fn run(&self, config: Config) -> Result<()> {
+        let mut lib = Library::open(&config)?;
+        let undone = lib.undo(self.n)?;
+        if undone.is_empty() {
+            println!("Nothing to undo.");
+        }
+        for (op, conflicts) in undone {
+            for info in conflicts {
+                println!(
+                    "Warning: {} has been changed since, these changes are lost.",
+                    info.title
+                );
+            }
+            println!("Undid {} {} {}", op.id, op.kind, op.summary());
+        }
+        Ok(())
+    }
+}
@@ -1,31 +1,37 @@
-use std::path::Path;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
 
 use reqwest::header::USER_AGENT;
 use tokio::io::AsyncWriteExt;
 
-use crate::library::LocalPaper;
+use crate::library::{Attachment, AttachmentRole, LocalPaper};
 use crate::{PaperInfo, PaperUrl};
 
 pub async fn async_download_and_save(
     metadata: PaperInfo,
     download_url: PaperUrl,
+    role: AttachmentRole,
     dest: &Path,
 ) -> Result<LocalPaper> {
-    download_pdf(&download_url.raw(), &dest).await?;
+    download_pdf(&download_url.raw(), dest).await?;
 
-    Ok(LocalPaper {
+    Ok(LocalPaper::new(
         metadata,
-        location: dest.to_path_buf(),
-        ees: vec![download_url],
-    })
+        vec![Attachment::new(dest, role)],
+        vec![download_url],
+    ))
 }
 
 async fn download_pdf(url: &str, out_path: &Path) -> Result<()> {
     let client = reqwest::Client::new();
     let response = client
-        .get(&*url)
+        .get(url)
         .header(USER_AGENT, "xivar")
         .send()
         .await
@@ -35,3 +41,141 @@ async fn download_pdf(url: &str, out_path: &Path) -> Result<()> {
     file.write_all(&body).await?;
     Ok(())
 }
+
+/// Opens `text` in `$VISUAL` or `$EDITOR` and returns the edited text.
+pub fn edit_in_editor(text: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_owned());
+
+    let mut file = tempfile::Builder::new()
+        .prefix("xivar-note-")
+        .suffix(".md")
+        .tempfile()?;
+    file.write_all(text.as_bytes())?;
+    file.flush()?;
+
+    let mut args = editor.split_whitespace();
+    let program = args.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(args)
+        .arg(file.path())
+        .status()
+        .with_context(|| format!("could not start editor {}", editor))?;
+    if !status.success() {
+        bail!("Editor {} exited with {}", editor, status);
+    }
+
+    Ok(std::fs::read_to_string(file.path())?)
+}
+
+/// Asks `question` on stdin and returns the trimmed answer, or `default` if the answer is empty.
+pub fn prompt(question: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(default) if !default.is_empty() => print!("{} [{}]: ", question, default),
+        _ => print!("{}: ", question),
+    }
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input)? == 0 {
+        bail!("Aborting!");
+    }
+    match input.trim() {
+        "" => Ok(default.unwrap_or_default().to_owned()),
+        answer => Ok(answer.to_owned()),
+    }
+}
+
+/// Asks a yes/no `question` on stdin, no by default.
+pub fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim(), "y" | "Y" | "yes"))
+}
+
+/// Asks on stdin which of `papers` to use and returns its position.
+pub fn select_paper(papers: &[LocalPaper]) -> Result<usize> {
+    for (i, paper) in papers.iter().enumerate() {
+        println!("({}) {}", i + 1, paper);
+    }
+    print!("Select a paper: ");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    match input.trim().parse::<usize>() {
+        Ok(i) if i >= 1 && i <= papers.len() => Ok(i - 1),
+        _ => bail!("Invalid selection!"),
+    }
+}
+
+/// Calls `f` with the path and size of every file below `dir`, without following symlinks.
+pub fn visit_files(dir: &Path, f: &mut impl FnMut(&Path, u64)) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::warn!("Could not read directory {:?}: {}", dir, err);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => visit_files(&entry.path(), f),
+            Ok(t) if t.is_file() => {
+                if let Ok(meta) = entry.metadata() {
+                    f(&entry.path(), meta.len());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `path`, or if it is `taken`, the first free path with a number appended to the file name,
+/// e.g. `paper-2.pdf`.
+pub fn unique_path(path: &Path, taken: impl Fn(&Path) -> bool) -> PathBuf {
+    if !taken(path) {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{}-{}{}", stem, n, extension)))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+/// Moves the file `from` to `to` and creates the missing directories, but never overwrites an
+/// existing file. Across file systems, the file is copied to a temporary file next to `to`
+/// first, so that `to` is either complete or does not exist.
+pub fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(dir) = to.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("could not create directory {}", dir.display()))?;
+    }
+    if to.exists() {
+        bail!("{} already exists", to.display());
+    }
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(err) if err.raw_os_error() == Some(libc::EXDEV) => {
+            let dir = to.parent().unwrap_or_else(|| Path::new("."));
+            let mut tmp = tempfile::NamedTempFile::new_in(dir)
+                .with_context(|| format!("could not create temporary file in {}", dir.display()))?;
+            let mut source = fs::File::open(from)
+                .with_context(|| format!("could not open {}", from.display()))?;
+            std::io::copy(&mut source, &mut tmp)
+                .with_context(|| format!("could not copy {}", from.display()))?;
+            tmp.as_file().sync_all()?;
+            tmp.persist_noclobber(to)
+                .with_context(|| format!("could not write {}", to.display()))?;
+            fs::remove_file(from).with_context(|| format!("could not remove {}", from.display()))
+        }
+        Err(err) => Err(err)
+            .with_context(|| format!("could not move {} to {}", from.display(), to.display())),
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use console::style;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::{
+    identify,
+    library::{
+        inbox::{self, InboxEntry},
+        Library,
+    },
+    pdf,
+    resolve::{self, Candidate},
+    xiv_config::Config,
+};
+
+/// Time without further changes after which a new file is considered complete.
+const SETTLE: Duration = Duration::from_secs(2);
+
+#[derive(Parser, Debug)]
+#[clap(about = "Watch your document directory and file new PDFs into your library")]
+pub struct Watch {}
+
+impl Watch {
+    pub async fn run(&self, config: Config) -> Result<()> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            tx.send(event).ok();
+        })?;
+
+        let mut watched = 0;
+        for dir in std::iter::once(&config.paper_dir).chain(&config.watch) {
+            if dir.as_os_str().is_empty() {
+                continue;
+            }
+            match dir.canonicalize() {
+                Ok(dir) if dir.is_dir() => {
+                    watcher
+                        .watch(&dir, RecursiveMode::Recursive)
+                        .with_context(|| format!("could not watch {}", dir.display()))?;
+                    println!("Watching {}", dir.display());
+                    watched += 1;
+                }
+                _ => println!("Skipping {}, which is no directory.", dir.display()),
+            }
+        }
+        if watched == 0 {
+            bail!("Nothing to watch, set `document_dir` or `watch` in your xivar.toml!");
+        }
+
+        let mut lib = Library::open(&config)?;
+        let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+        loop {
+            tokio::select! {
+                event = rx.recv() => match event {
+                    Some(Ok(event)) => {
+                        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                            for path in event.paths.into_iter().filter(|p| is_pdf(p)) {
+                                pending.insert(path, Instant::now());
+                            }
+                        }
+                    }
+                    Some(Err(err)) => log::warn!("Watch error: {}", err),
+                    None => break,
+                },
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+
+            let settled: Vec<PathBuf> = pending
+                .iter()
+                .filter(|(_, changed)| changed.elapsed() >= SETTLE)
+                .map(|(path, _)| path.clone())
+                .collect();
+            for path in settled {
+                pending.remove(&path);
+                if let Err(err) = file_pdf(&mut lib, &config.data_dir, &path).await {
+                    println!(
+                        "{} {}: {}",
+                        style("Error").red().bold(),
+                        path.display(),
+                        err
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// Adds the PDF at `path` to the library if it can be found online with confidence, and
+/// queues it in the inbox otherwise.
+async fn file_pdf(lib: &mut Library, data_dir: &Path, path: &Path) -> Result<()> {
+    // the file may have been added by another xivar since the library was loaded
+    lib.reload()?;
+    if !path.is_file()
+        || lib.find_paper_by_path(path).is_some()
+        || inbox::read(data_dir)?.iter().any(|e| e.path == path)
+    {
+        return Ok(());
+    }
+
+    let info = match pdf::read_info(path) {
+        Ok(info) => info,
+        Err(err) => return queue(data_dir, path, &format!("{:#}", err), vec![]),
+    };
+    let ids = identify::identifiers(path, &info);
+    for found in ids.iter().filter(|found| found.source.is_reliable()) {
+        match resolve::lookup(&found.id, path).await {
+            Ok(Some(paper)) => {
+                println!("{} {}", style("Filed").green().bold(), paper.metadata());
+                lib.add(paper);
+                return lib.save();
+            }
+            Ok(None) => {}
+            Err(err) => log::warn!("Could not look up {}: {}", found.id, err),
+        }
+    }
+    let title = match identify::title(&info) {
+        Some(title) => title,
+        None => return queue(data_dir, path, "no identifier or title found", vec![]),
+    };
+
+    let mut candidates = match resolve::search(&title, &info.authors, path).await {
+        Ok(candidates) => candidates,
+        Err(err) => return queue(data_dir, path, &err.to_string(), vec![]),
+    };
+    match candidates.first() {
+        Some(best) if best.score >= resolve::CONFIDENT => {
+            println!(
+                "{} {}",
+                style("Filed").green().bold(),
+                best.paper.metadata()
+            );
+            lib.add(best.paper.clone());
+            lib.save()
+        }
+        Some(_) => {
+            candidates.truncate(5);
+            queue(data_dir, path, "no confident match online", candidates)
+        }
+        None => queue(data_dir, path, "not found online", vec![]),
+    }
+}
+
+fn queue(data_dir: &Path, path: &Path, reason: &str, candidates: Vec<Candidate>) -> Result<()> {
+    inbox::update(data_dir, |entries| {
+        entries.push(InboxEntry::new(path, reason, candidates))
+    })?;
+    println!(
+        "{} {} ({})",
+        style("Inbox").yellow().bold(),
+        path.display(),
+        reason
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        library::{Attachment, AttachmentRole, Backend, LocalPaper},
+        PaperInfo, PaperTitle, Venue,
+    };
+
+    #[tokio::test]
+    async fn skip_files_saved_by_another_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let path = dir.path().join("download.pdf");
+        let mut lib = Library::open_with(Backend::Bincode, &data_dir).unwrap();
+
+        // e.g. the interactive mode downloads a paper while `xivar watch` is running
+        let mut other = Library::open_with(Backend::Bincode, &data_dir).unwrap();
+        fs::write(&path, "not a pdf").unwrap();
+        let info = PaperInfo {
+            id: None,
+            title: PaperTitle::new("Learning-Augmented Online Algorithms".to_owned()),
+            venue: Venue::Conf("SODA".to_owned()),
+            authors: vec!["Alexander Lindermayr".to_owned()],
+            year: "2022".to_owned(),
+            summary: None,
+        };
+        let attachment = Attachment::new(&path, AttachmentRole::Main);
+        other.add(LocalPaper::new(info, vec![attachment], vec![]));
+        other.save().unwrap();
+
+        file_pdf(&mut lib, &data_dir, &path).await.unwrap();
+        assert!(lib.find_paper_by_path(&path).is_some());
+        assert!(inbox::read(&data_dir).unwrap().is_empty());
+    }
+}
@@ -2,9 +2,26 @@ use std::path::PathBuf;
 
 use anyhow::{bail, Result};
 
+use crate::{
+    library::{backup, Backend},
+    template::Template,
+};
+
+#[derive(Clone, Debug)]
 pub struct Config {
     pub data_dir: PathBuf,
     pub paper_dir: PathBuf,
+    pub backend: Backend,
+    /// Directories besides `paper_dir` which are searched for moved files.
+    pub roots: Vec<PathBuf>,
+    /// Number of snapshots of the library kept in `data_dir/backups`.
+    pub backups: usize,
+    /// Directories besides `paper_dir` which `xivar watch` monitors for new PDFs.
+    pub watch: Vec<PathBuf>,
+    /// Scheme of the citation keys in exports, the keys of the library if `None`.
+    pub citekey: Option<Template>,
+    /// Path of downloaded papers relative to `paper_dir`, without extension.
+    pub filename: Template,
 }
 
 pub fn load_config() -> Result<Config> {
@@ -38,10 +55,25 @@ pub fn load_config() -> Result<Config> {
         .add_source(config::File::from(config_file))
         .set_default("document_dir", "")?
         .set_default("data_dir", data_dir.as_os_str().to_str())?
+        .set_default("backend", "bincode")?
+        .set_default("roots", Vec::<String>::new())?
+        .set_default("backups", backup::DEFAULT_KEEP as u64)?
+        .set_default("watch", Vec::<String>::new())?
+        .set_default("citekey", "")?
+        .set_default("filename", "{compact}")?
         .build()?;
 
     Ok(Config {
         data_dir: settings.get::<PathBuf>("data_dir").unwrap(),
         paper_dir: settings.get::<PathBuf>("document_dir").unwrap(),
+        backend: settings.get::<Backend>("backend")?,
+        roots: settings.get::<Vec<PathBuf>>("roots")?,
+        backups: settings.get::<usize>("backups")?,
+        watch: settings.get::<Vec<PathBuf>>("watch")?,
+        citekey: match settings.get::<String>("citekey")?.as_str() {
+            "" => None,
+            template => Some(template.parse()?),
+        },
+        filename: settings.get::<String>("filename")?.parse()?,
     })
 }